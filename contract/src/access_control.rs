@@ -0,0 +1,282 @@
+//! Roles and ownership.
+//!
+//! The owner implicitly holds every role. Admins manage the moderators and treasurers, while only
+//! the owner grants and revokes the admin role. The ownership itself is transferred in two steps:
+//! the owner proposes a new owner and the proposed account accepts. The supply minted to the owner
+//! at initialization moves to the new owner together with the ownership, reputation the previous
+//! owner earned as author stays with them.
+
+use crate::*;
+use near_contract_standards::fungible_token::events::FtTransfer;
+
+/// The Borsh encoding of a role is part of the key of its members, so roles are only ever appended.
+#[derive(BorshDeserialize, BorshSerialize, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    Owner,
+    /// Configures the contract and manages moderators and treasurers.
+    Admin,
+    /// Reviews reports of deeds.
+    Moderator,
+    /// Manages the funds held by the contract.
+    Treasurer,
+}
+
+impl Role {
+    fn name(&self) -> &'static str {
+        match self {
+            Role::Owner => "owner",
+            Role::Admin => "admin",
+            Role::Moderator => "moderator",
+            Role::Treasurer => "treasurer",
+        }
+    }
+}
+
+impl Contract {
+    pub(crate) fn assert_owner(&self) {
+        assert_eq!(env::predecessor_account_id(), self.owner, "Only the owner can call this method.");
+    }
+
+    /// Panics if the predecessor does not hold `role`.
+    pub(crate) fn assert_role(&self, role: Role) {
+        assert!(self.has_role(role, env::predecessor_account_id()), "Only accounts with the {} role can call this method.", role.name());
+    }
+
+    /// Panics if the predecessor cannot grant or revoke `role`.
+    fn assert_can_manage(&self, role: Role) {
+        require!(role != Role::Owner, "The ownership can only be transferred with transfer_ownership.");
+        if role == Role::Admin {
+            self.assert_owner();
+        } else {
+            self.assert_role(Role::Admin);
+        }
+    }
+
+    fn role_members(&self, role: Role) -> UnorderedSet<AccountId> {
+        self.roles.get(&role).unwrap_or_else(|| UnorderedSet::new(StorageKey::RoleMembers { role }))
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Grants `role` to `account_id`. Admins are granted by the owner, all other roles by admins.
+    #[payable]
+    pub fn grant_role(&mut self, role: Role, account_id: AccountId) {
+        let initial_storage_usage = env::storage_usage();
+
+        self.assert_can_manage(role);
+        let mut members = self.role_members(role);
+        if members.insert(&account_id) {
+            self.roles.insert(&role, &members);
+            events::RoleGranted { role, account_id: &account_id, by: &env::predecessor_account_id() }.emit();
+        }
+
+        refund_deposit(env::storage_usage() - initial_storage_usage);
+    }
+
+    /// Revokes `role` from `account_id`. Admins are revoked by the owner, all other roles by admins.
+    pub fn revoke_role(&mut self, role: Role, account_id: AccountId) {
+        self.assert_can_manage(role);
+        let mut members = self.role_members(role);
+        if members.remove(&account_id) {
+            self.roles.insert(&role, &members);
+            events::RoleRevoked { role, account_id: &account_id, by: &env::predecessor_account_id() }.emit();
+        }
+    }
+
+    /// Proposes `new_owner` as the next owner of the contract. The transfer is completed when the
+    /// new owner calls `accept_ownership`. `None` cancels a pending transfer.
+    pub fn transfer_ownership(&mut self, new_owner: Option<AccountId>) {
+        self.assert_owner();
+        if let Some(new_owner) = &new_owner {
+            require!(new_owner != &self.owner, "The account is already the owner.");
+            events::OwnershipTransferStarted { owner: &self.owner, pending_owner: new_owner }.emit();
+        }
+        self.pending_owner = new_owner;
+    }
+
+    /// Completes the transfer of the ownership to the caller. The pre-minted supply the previous
+    /// owner still holds is transferred to the caller, which is registered if needed.
+    #[payable]
+    pub fn accept_ownership(&mut self) {
+        let initial_storage_usage = env::storage_usage();
+
+        let new_owner = env::predecessor_account_id();
+        require!(self.pending_owner.as_ref() == Some(&new_owner), "Only the pending owner can accept the ownership.");
+        // Keep what the new owner accrued as regular holder, the owner does not take part in
+        // donations anymore.
+        self.internal_settle_donations(&new_owner);
+        if !self.token.accounts.contains_key(&new_owner) {
+            self.token.internal_register_account(&new_owner);
+        }
+        let old_owner = std::mem::replace(&mut self.owner, new_owner.clone());
+        self.pending_owner = None;
+        let balance: Balance = self.token.ft_balance_of(old_owner.clone()).into();
+        let supply = self.owner_supply.min(balance);
+        self.owner_supply = supply;
+        if supply > 0 {
+            self.token.internal_transfer(&old_owner, &new_owner, supply, None);
+            FtTransfer {
+                old_owner_id: &old_owner,
+                new_owner_id: &new_owner,
+                amount: &supply.into(),
+                memo: Some("Supply of the owner moves to the new owner"),
+            }
+            .emit();
+        }
+        // The previous owner takes part in donations from now on, but its balance was not part
+        // of the earlier ones.
+        self.internal_skip_donations(&old_owner);
+        events::OwnershipTransferred { old_owner: &old_owner, new_owner: &new_owner }.emit();
+
+        refund_deposit(env::storage_usage() - initial_storage_usage);
+    }
+
+    pub fn get_owner(&self) -> AccountId {
+        self.owner.clone()
+    }
+
+    pub fn get_pending_owner(&self) -> Option<AccountId> {
+        self.pending_owner.clone()
+    }
+
+    /// Returns whether `account_id` holds `role`. The owner holds every role.
+    pub fn has_role(&self, role: Role, account_id: AccountId) -> bool {
+        account_id == self.owner || self.roles.get(&role).is_some_and(|members| members.contains(&account_id))
+    }
+
+    /// Returns the accounts `role` was granted to. The owner is not listed unless the role was
+    /// granted to it explicitly.
+    pub fn role_holders(&self, role: Role, from_index: Option<u64>, limit: Option<u64>) -> Vec<AccountId> {
+        if role == Role::Owner {
+            return vec![self.owner.clone()];
+        }
+        let start_index = from_index.unwrap_or_default();
+        let limit = limit.unwrap_or(u64::MAX);
+        require!(limit != 0, "Cannot provide limit of 0.");
+        let members = self.role_members(role);
+        let members = members.as_vector();
+        (start_index..members.len())
+            .take(limit as usize)
+            .map(|index| members.get(index).unwrap())
+            .collect()
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_sdk::test_utils::accounts;
+    use near_sdk::testing_env;
+
+    use crate::tests::{call, get_context, ONE_NEAR, SAFE_STORAGE_COST, TOTAL_SUPPLY};
+    use super::*;
+
+    #[test]
+    fn test_admin_grants_moderator() {
+        let mut context = get_context(accounts(2));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into());
+        call(&mut context, accounts(2), SAFE_STORAGE_COST);
+        contract.grant_role(Role::Admin, accounts(3));
+        call(&mut context, accounts(3), SAFE_STORAGE_COST);
+        contract.grant_role(Role::Moderator, accounts(4));
+
+        assert!(contract.has_role(Role::Moderator, accounts(4)));
+        assert!(contract.has_role(Role::Moderator, accounts(2)));
+        assert!(!contract.has_role(Role::Moderator, accounts(3)));
+        assert_eq!(contract.role_holders(Role::Moderator, None, None), vec![accounts(4)]);
+
+        contract.revoke_role(Role::Moderator, accounts(4));
+        assert!(!contract.has_role(Role::Moderator, accounts(4)));
+        assert!(contract.role_holders(Role::Moderator, None, None).is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the owner can call this method.")]
+    fn test_admin_cannot_grant_admin() {
+        let mut context = get_context(accounts(2));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into());
+        call(&mut context, accounts(2), SAFE_STORAGE_COST);
+        contract.grant_role(Role::Admin, accounts(3));
+        call(&mut context, accounts(3), SAFE_STORAGE_COST);
+        contract.grant_role(Role::Admin, accounts(4));
+    }
+
+    #[test]
+    #[should_panic(expected = "Only accounts with the admin role can call this method.")]
+    fn test_only_admins_grant_moderator() {
+        let mut context = get_context(accounts(2));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into());
+        call(&mut context, accounts(3), SAFE_STORAGE_COST);
+        contract.grant_role(Role::Moderator, accounts(4));
+    }
+
+    #[test]
+    fn test_transfer_ownership_moves_supply() {
+        let mut context = get_context(accounts(2));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into());
+        call(&mut context, accounts(2), SAFE_STORAGE_COST);
+        contract.add_deed(accounts(2), "title".into(), "description".into(), "proof".into(), None, None);
+        call(&mut context, accounts(4), SAFE_STORAGE_COST);
+        contract.storage_deposit(None, None);
+        contract.credit(0);
+
+        call(&mut context, accounts(2), 0);
+        contract.transfer_ownership(Some(accounts(3)));
+        assert_eq!(contract.get_pending_owner(), Some(accounts(3)));
+        assert_eq!(contract.get_owner(), accounts(2));
+
+        call(&mut context, accounts(3), SAFE_STORAGE_COST);
+        contract.accept_ownership();
+        assert_eq!(contract.get_owner(), accounts(3));
+        assert_eq!(contract.get_pending_owner(), None);
+        assert_eq!(contract.ft_balance_of(accounts(3)).0, TOTAL_SUPPLY);
+        // The reputation earned as author stays with the previous owner.
+        assert_eq!(contract.ft_balance_of(accounts(2)).0, 1);
+        assert_eq!(contract.donatable_supply(), 1);
+        assert!(near_sdk::test_utils::get_logs().iter().any(|log| log.contains("\"event\":\"ft_transfer\"")));
+    }
+
+    #[test]
+    fn test_previous_owner_does_not_accrue_earlier_donations() {
+        let mut context = get_context(accounts(2));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into());
+        call(&mut context, accounts(1), SAFE_STORAGE_COST);
+        contract.storage_deposit(None, None);
+        contract.add_deed(accounts(1), "title".into(), "description".into(), "proof".into(), None, None);
+        call(&mut context, accounts(2), SAFE_STORAGE_COST);
+        contract.add_deed(accounts(2), "title".into(), "description".into(), "proof".into(), None, None);
+        call(&mut context, accounts(4), SAFE_STORAGE_COST);
+        contract.storage_deposit(None, None);
+        contract.credit(0);
+        contract.credit(1);
+        call(&mut context, accounts(4), ONE_NEAR);
+        contract.donate();
+
+        call(&mut context, accounts(2), 0);
+        contract.transfer_ownership(Some(accounts(3)));
+        call(&mut context, accounts(3), SAFE_STORAGE_COST);
+        contract.accept_ownership();
+        assert_eq!(contract.ft_balance_of(accounts(2)).0, 1);
+        assert_eq!(contract.claimable_donations(accounts(2)).0, 0);
+        assert!(contract.claimable_donations(accounts(1)).0 > 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the pending owner can accept the ownership.")]
+    fn test_only_pending_owner_accepts_ownership() {
+        let mut context = get_context(accounts(2));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into());
+        contract.transfer_ownership(Some(accounts(3)));
+
+        call(&mut context, accounts(4), SAFE_STORAGE_COST);
+        contract.accept_ownership();
+    }
+}
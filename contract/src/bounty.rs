@@ -0,0 +1,356 @@
+//! Escrowed bounties.
+//!
+//! A sponsor posts a task together with a NEAR reward. Hunters submit their deeds to the bounty
+//! and the sponsor accepts the submissions that solve it. Every accepted submission pays an equal
+//! share of the escrow to the author of the deed. Whatever has not been paid out when the deadline
+//! has passed can be refunded to the sponsor.
+
+use crate::*;
+use crate::pause::Feature;
+use near_sdk::json_types::U64;
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct Bounty {
+    id: u64,
+    sponsor: AccountId,
+    title: String,
+    description: String,
+    /// Block timestamp in nanoseconds after which no submissions are accepted anymore.
+    deadline: u64,
+    max_winners: u64,
+    /// The escrowed reward, i.e. the attached deposit without the storage costs.
+    reward: Balance,
+    paid: Balance,
+    refunded: bool,
+    submissions: UnorderedSet<u64>,
+    winners: UnorderedSet<u64>
+}
+
+impl Bounty {
+    pub fn new(
+        id: u64,
+        sponsor: AccountId,
+        title: String,
+        description: String,
+        deadline: u64,
+        max_winners: u64
+    ) -> Self{
+        Self {
+            id, sponsor, title, description, deadline, max_winners,
+            reward: 0,
+            paid: 0,
+            refunded: false,
+            submissions: UnorderedSet::new(StorageKey::BountySubmissions { bounty_id: id }),
+            winners: UnorderedSet::new(StorageKey::BountyWinners { bounty_id: id })
+        }
+    }
+
+    /// The amount every accepted submission receives.
+    pub fn share(&self) -> Balance {
+        self.reward / Balance::from(self.max_winners)
+    }
+
+    pub fn is_open(&self) -> bool {
+        !self.refunded && env::block_timestamp() <= self.deadline
+    }
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct SerializableBounty {
+    id: u64,
+    sponsor: AccountId,
+    title: String,
+    description: String,
+    deadline: U64,
+    max_winners: u64,
+    reward: U128,
+    paid: U128,
+    refunded: bool,
+    submissions: u64,
+    winners: u64
+}
+
+impl From<&Bounty> for SerializableBounty {
+    fn from(bounty: &Bounty) -> Self {
+        Self {
+            id: bounty.id,
+            sponsor: bounty.sponsor.clone(),
+            title: bounty.title.clone(),
+            description: bounty.description.clone(),
+            deadline: bounty.deadline.into(),
+            max_winners: bounty.max_winners,
+            reward: bounty.reward.into(),
+            paid: bounty.paid.into(),
+            refunded: bounty.refunded,
+            submissions: bounty.submissions.len(),
+            winners: bounty.winners.len()
+        }
+    }
+}
+
+impl Contract {
+    fn internal_get_bounty(&self, bounty_id: u64) -> Bounty {
+        assert!(self.bounties.len() > bounty_id, "The bounty id is out of range.");
+        self.bounties.get(bounty_id).unwrap()
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Posts a new bounty. The attached deposit minus the storage costs is escrowed as reward and
+    /// split equally between up to `max_winners` accepted submissions.
+    #[payable]
+    pub fn create_bounty(
+        &mut self,
+        title: String,
+        description: String,
+        deadline: U64,
+        max_winners: u64
+    ) -> u64
+    {
+        let initial_storage_usage = env::storage_usage();
+
+        self.assert_not_paused(Feature::Donations);
+        require!(deadline.0 > env::block_timestamp(), "The deadline must be in the future.");
+        require!(max_winners > 0, "A bounty needs at least one winner.");
+        let id = self.bounties.len();
+        let mut bounty = Bounty::new(id, env::predecessor_account_id(), title, description, deadline.0, max_winners);
+        self.bounties.push(&bounty);
+
+        bounty.reward = calculate_and_check_deposit(env::storage_usage() - initial_storage_usage);
+        require!(bounty.share() > 0, "The attached deposit does not cover a reward for every winner.");
+        self.bounties.replace(id, &bounty);
+        events::BountyCreated { bounty_id: id, sponsor: &bounty.sponsor, reward: &bounty.reward.into() }.emit();
+        id
+    }
+
+    /// Submits the deed `deed_id` of the caller as a solution of the bounty `bounty_id`.
+    #[payable]
+    pub fn submit_to_bounty(
+        &mut self,
+        bounty_id: u64,
+        deed_id: u64
+    )
+    {
+        let initial_storage_usage = env::storage_usage();
+
+        self.assert_not_paused(Feature::Donations);
+        let mut bounty = self.internal_get_bounty(bounty_id);
+        require!(bounty.is_open(), "The bounty does not accept submissions anymore.");
+        let deed = self.internal_get_deed(deed_id);
+        deed.assert_active();
+        assert_eq!(deed.author, env::predecessor_account_id(), "Only the author can submit a deed.");
+        assert!(bounty.submissions.insert(&deed_id), "The deed was already submitted to the bounty.");
+        self.bounties.replace(bounty_id, &bounty);
+        events::BountySubmitted { bounty_id, deed_id, author: &deed.author }.emit();
+
+        refund_deposit(env::storage_usage() - initial_storage_usage);
+    }
+
+    /// Accepts the submission `deed_id` of the bounty `bounty_id` and pays the share of the escrow
+    /// to the author of the deed. Can only be called by the sponsor and only while the deed is
+    /// active.
+    pub fn accept_submission(
+        &mut self,
+        bounty_id: u64,
+        deed_id: u64
+    )
+    {
+        self.assert_not_paused(Feature::Donations);
+        let mut bounty = self.internal_get_bounty(bounty_id);
+        assert_eq!(bounty.sponsor, env::predecessor_account_id(), "Only the sponsor can accept submissions.");
+        require!(!bounty.refunded, "The bounty was already refunded.");
+        require!(bounty.submissions.contains(&deed_id), "The deed was not submitted to the bounty.");
+        let deed = self.internal_get_deed(deed_id);
+        deed.assert_active();
+        require!(bounty.winners.len() < bounty.max_winners, "All winners of the bounty were already accepted.");
+        assert!(bounty.winners.insert(&deed_id), "The submission was already accepted.");
+        let share = bounty.share();
+        bounty.paid += share;
+        self.bounties.replace(bounty_id, &bounty);
+
+        events::BountyPaid { bounty_id, deed_id, author: &deed.author, amount: &share.into() }.emit();
+        Promise::new(deed.author).transfer(share);
+    }
+
+    /// Refunds the part of the escrow that was not paid out to the sponsor once the deadline has
+    /// passed and closes the bounty.
+    pub fn refund_bounty(
+        &mut self,
+        bounty_id: u64
+    ) -> U128
+    {
+        self.assert_not_paused(Feature::Donations);
+        let mut bounty = self.internal_get_bounty(bounty_id);
+        assert_eq!(bounty.sponsor, env::predecessor_account_id(), "Only the sponsor can refund the bounty.");
+        require!(env::block_timestamp() > bounty.deadline, "The bounty can only be refunded after the deadline.");
+        require!(!bounty.refunded, "The bounty was already refunded.");
+        let refund = bounty.reward - bounty.paid;
+        bounty.refunded = true;
+        self.bounties.replace(bounty_id, &bounty);
+
+        if refund > 0 {
+            events::BountyRefunded { bounty_id, sponsor: &bounty.sponsor, amount: &refund.into() }.emit();
+            Promise::new(bounty.sponsor).transfer(refund);
+        }
+        refund.into()
+    }
+
+    pub fn get_bounties_count(&self) -> u64 {
+        self.bounties.len()
+    }
+
+    pub fn get_bounty(&self, bounty_id: u64) -> SerializableBounty {
+        (&self.internal_get_bounty(bounty_id)).into()
+    }
+
+    pub fn bounties(&self, from_index: Option<u64>, limit: Option<u64>) -> Vec<SerializableBounty> {
+        let start_index = from_index.unwrap_or_default();
+        let limit = limit.unwrap_or(u64::MAX);
+        require!(limit != 0, "Cannot provide limit of 0.");
+        (start_index..self.bounties.len())
+            .take(limit as usize)
+            .map(|id| (&self.bounties.get(id).unwrap()).into())
+            .collect()
+    }
+
+    /// Returns the ids of the deeds submitted to the bounty `bounty_id` together with the
+    /// information whether they were accepted.
+    pub fn bounty_submissions(&self, bounty_id: u64, from_index: Option<u64>, limit: Option<u64>) -> Vec<(u64, bool)> {
+        let bounty = self.internal_get_bounty(bounty_id);
+        let start_index = from_index.unwrap_or_default();
+        let limit = limit.unwrap_or(u64::MAX);
+        require!(limit != 0, "Cannot provide limit of 0.");
+        let submissions = bounty.submissions.as_vector();
+        (start_index..submissions.len())
+            .take(limit as usize)
+            .map(|index| {
+                let deed_id = submissions.get(index).unwrap();
+                (deed_id, bounty.winners.contains(&deed_id))
+            })
+            .collect()
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::testing_env;
+
+    use crate::tests::{call, get_context, SAFE_STORAGE_COST, TOTAL_SUPPLY};
+    use super::*;
+
+    const DEADLINE: u64 = 1_000_000;
+
+    /// Sets up a contract with a deed of accounts(1) and a bounty of accounts(3) with two winners.
+    fn setup(context: &mut VMContextBuilder) -> Contract {
+        testing_env!(context.block_timestamp(0).build());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into());
+        call(context, accounts(1), SAFE_STORAGE_COST);
+        contract.storage_deposit(None, None);
+        contract.add_deed(accounts(1), "title".into(), "description".into(), "proof".into(), None, None);
+
+        call(context, accounts(3), 10u128.pow(24));
+        contract.create_bounty("bounty".into(), "description".into(), DEADLINE.into(), 2);
+        contract
+    }
+
+    #[test]
+    fn test_accepted_submission_is_paid() {
+        let mut context = get_context(accounts(2));
+        let mut contract = setup(&mut context);
+        let reward = contract.get_bounty(0).reward.0;
+        assert!(reward > 0 && reward < 10u128.pow(24));
+
+        call(context.block_timestamp(1), accounts(1), SAFE_STORAGE_COST);
+        contract.submit_to_bounty(0, 0);
+        call(context.block_timestamp(2), accounts(3), 0);
+        contract.accept_submission(0, 0);
+
+        let bounty = contract.get_bounty(0);
+        assert_eq!(bounty.paid.0, reward / 2);
+        assert_eq!(bounty.winners, 1);
+        assert_eq!(contract.bounty_submissions(0, None, None), vec![(0, true)]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the author can submit a deed.")]
+    fn test_cannot_submit_deed_of_other_author() {
+        let mut context = get_context(accounts(2));
+        let mut contract = setup(&mut context);
+
+        call(context.block_timestamp(1), accounts(3), SAFE_STORAGE_COST);
+        contract.submit_to_bounty(0, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "The bounty does not accept submissions anymore.")]
+    fn test_cannot_submit_after_deadline() {
+        let mut context = get_context(accounts(2));
+        let mut contract = setup(&mut context);
+
+        call(context.block_timestamp(DEADLINE + 1), accounts(1), SAFE_STORAGE_COST);
+        contract.submit_to_bounty(0, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "The feature donations is paused.")]
+    fn test_cannot_submit_while_donations_are_paused() {
+        let mut context = get_context(accounts(2));
+        let mut contract = setup(&mut context);
+
+        call(context.block_timestamp(1), accounts(2), 0);
+        contract.pause(vec![Feature::Donations]);
+        call(context.block_timestamp(1), accounts(1), SAFE_STORAGE_COST);
+        contract.submit_to_bounty(0, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "The deed is not active anymore.")]
+    fn test_cannot_accept_retracted_deed() {
+        let mut context = get_context(accounts(2));
+        let mut contract = setup(&mut context);
+
+        call(context.block_timestamp(1), accounts(1), SAFE_STORAGE_COST);
+        contract.submit_to_bounty(0, 0);
+        contract.retract_deed(0);
+        call(context.block_timestamp(2), accounts(3), 0);
+        contract.accept_submission(0, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the sponsor can accept submissions.")]
+    fn test_only_sponsor_accepts_submissions() {
+        let mut context = get_context(accounts(2));
+        let mut contract = setup(&mut context);
+
+        call(context.block_timestamp(1), accounts(1), SAFE_STORAGE_COST);
+        contract.submit_to_bounty(0, 0);
+        contract.accept_submission(0, 0);
+    }
+
+    #[test]
+    fn test_refund_returns_unpaid_escrow() {
+        let mut context = get_context(accounts(2));
+        let mut contract = setup(&mut context);
+        let reward = contract.get_bounty(0).reward.0;
+
+        call(context.block_timestamp(1), accounts(1), SAFE_STORAGE_COST);
+        contract.submit_to_bounty(0, 0);
+        call(context.block_timestamp(DEADLINE + 1), accounts(3), 0);
+        contract.accept_submission(0, 0);
+        assert_eq!(contract.refund_bounty(0).0, reward - reward / 2);
+        assert!(contract.get_bounty(0).refunded);
+    }
+
+    #[test]
+    #[should_panic(expected = "The bounty can only be refunded after the deadline.")]
+    fn test_cannot_refund_before_deadline() {
+        let mut context = get_context(accounts(2));
+        let mut contract = setup(&mut context);
+
+        call(context.block_timestamp(DEADLINE), accounts(3), 0);
+        contract.refund_bounty(0);
+    }
+}
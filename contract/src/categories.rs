@@ -0,0 +1,211 @@
+//! Categories and tags of deeds.
+//!
+//! The owner maintains the registry of categories. Authors choose one registered category and a
+//! few free-form tags for every deed. Both are indexed, so the deeds of a category or tag can be
+//! listed without iterating over all deeds. Retracted deeds are removed from the indexes.
+
+use crate::*;
+
+/// Maximum number of tags of a deed.
+pub const MAX_TAGS: usize = 5;
+/// Maximum length of a tag in bytes.
+pub const MAX_TAG_LENGTH: usize = 32;
+
+/// Trims and lowercases `tags` and removes duplicates. Panics if the tags exceed the limits.
+pub(crate) fn normalize_tags(tags: Vec<String>) -> Vec<String> {
+    let mut normalized: Vec<String> = Vec::with_capacity(tags.len());
+    for tag in tags {
+        let tag = tag.trim().to_lowercase();
+        require!(!tag.is_empty() && tag.len() <= MAX_TAG_LENGTH, format!("A tag must have between 1 and {} bytes.", MAX_TAG_LENGTH));
+        if !normalized.contains(&tag) {
+            normalized.push(tag);
+        }
+    }
+    require!(normalized.len() <= MAX_TAGS, format!("A deed can have at most {} tags.", MAX_TAGS));
+    normalized
+}
+
+impl Contract {
+    pub(crate) fn assert_category(&self, category: &str) {
+        assert!(self.categories.contains(&category.to_string()), "The category {} does not exist.", category);
+    }
+
+    fn category_index(&self, category: &str) -> UnorderedSet<u64> {
+        self.deeds_by_category.get(&category.to_string())
+            .unwrap_or_else(|| UnorderedSet::new(StorageKey::CategoryDeeds { category: category.to_string() }))
+    }
+
+    fn tag_index(&self, tag: &str) -> UnorderedSet<u64> {
+        self.deeds_by_tag.get(&tag.to_string())
+            .unwrap_or_else(|| UnorderedSet::new(StorageKey::TagDeeds { tag: tag.to_string() }))
+    }
+
+    /// Adds `deed` to the indexes of its category and tags.
+    pub(crate) fn internal_index_deed(&mut self, deed: &SocialDeed) {
+        if let Some(category) = &deed.category {
+            let mut index = self.category_index(category);
+            index.insert(&deed.id);
+            self.deeds_by_category.insert(category, &index);
+        }
+        for tag in &deed.tags {
+            let mut index = self.tag_index(tag);
+            index.insert(&deed.id);
+            self.deeds_by_tag.insert(tag, &index);
+        }
+    }
+
+    /// Removes `deed` from the indexes of its category and tags.
+    pub(crate) fn internal_unindex_deed(&mut self, deed: &SocialDeed) {
+        if let Some(category) = &deed.category {
+            let mut index = self.category_index(category);
+            index.remove(&deed.id);
+            self.deeds_by_category.insert(category, &index);
+        }
+        for tag in &deed.tags {
+            let mut index = self.tag_index(tag);
+            index.remove(&deed.id);
+            if index.is_empty() {
+                self.deeds_by_tag.remove(tag);
+            } else {
+                self.deeds_by_tag.insert(tag, &index);
+            }
+        }
+    }
+
+    fn deeds_of_index(&self, index: UnorderedSet<u64>, creditor_id: Option<AccountId>, from_index: Option<u64>,
+                      limit: Option<u64>) -> Vec<SerializableDeed> {
+        let start_index = from_index.unwrap_or_default();
+        let limit = limit.unwrap_or(u64::MAX);
+        require!(limit != 0, "Cannot provide limit of 0.");
+        let ids = index.as_vector();
+        (start_index..ids.len())
+            .take(limit as usize)
+            .map(|index| SerializableDeed::new(self.internal_get_deed(ids.get(index).unwrap()), creditor_id.as_ref()))
+            .collect()
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Adds `category` to the registry. Can only be called by the owner.
+    #[payable]
+    pub fn add_category(&mut self, category: String) {
+        let initial_storage_usage = env::storage_usage();
+
+        self.assert_owner();
+        require!(!category.is_empty() && category.len() <= MAX_TAG_LENGTH, format!("A category must have between 1 and {} bytes.", MAX_TAG_LENGTH));
+        assert!(self.categories.insert(&category), "The category {} already exists.", category);
+
+        refund_deposit(env::storage_usage() - initial_storage_usage);
+    }
+
+    /// Removes `category` from the registry. Only categories without deeds can be removed. Can
+    /// only be called by the owner.
+    pub fn remove_category(&mut self, category: String) {
+        self.assert_owner();
+        self.assert_category(&category);
+        require!(self.category_index(&category).is_empty(), "A category with deeds cannot be removed.");
+        self.categories.remove(&category);
+        self.deeds_by_category.remove(&category);
+    }
+
+    pub fn get_categories(&self) -> Vec<String> {
+        self.categories.to_vec()
+    }
+
+    /// Returns the deeds of `category`. `is_creditor` refers to `creditor_id` if it is given.
+    pub fn deeds_by_category(&self, category: String, from_index: Option<u64>, limit: Option<u64>,
+                             creditor_id: Option<AccountId>) -> Vec<SerializableDeed> {
+        self.assert_category(&category);
+        self.deeds_of_index(self.category_index(&category), creditor_id, from_index, limit)
+    }
+
+    /// Returns the deeds tagged with `tag`. `is_creditor` refers to `creditor_id` if it is given.
+    pub fn deeds_by_tag(&self, tag: String, from_index: Option<u64>, limit: Option<u64>,
+                        creditor_id: Option<AccountId>) -> Vec<SerializableDeed> {
+        let tag = tag.trim().to_lowercase();
+        self.deeds_of_index(self.tag_index(&tag), creditor_id, from_index, limit)
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::testing_env;
+
+    use crate::tests::{call, get_context, SAFE_STORAGE_COST, TOTAL_SUPPLY};
+    use super::*;
+
+    /// Sets up a contract with the categories `environment` and `education`.
+    fn setup(context: &mut VMContextBuilder) -> Contract {
+        testing_env!(context.attached_deposit(SAFE_STORAGE_COST).build());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into());
+        contract.add_category("environment".into());
+        contract.add_category("education".into());
+        call(context, accounts(1), 10 * SAFE_STORAGE_COST);
+        contract
+    }
+
+    fn add_deed(contract: &mut Contract, category: &str, tags: &[&str]) {
+        let tags = tags.iter().map(|tag| tag.to_string()).collect();
+        contract.add_deed(accounts(1), "title".into(), "description".into(), "proof".into(), Some(category.into()), Some(tags));
+    }
+
+    #[test]
+    fn test_deeds_by_category_and_tag() {
+        let mut context = get_context(accounts(2));
+        let mut contract = setup(&mut context);
+        add_deed(&mut contract, "environment", &["Trees", "trees ", "city"]);
+        add_deed(&mut contract, "education", &["city"]);
+        add_deed(&mut contract, "environment", &[]);
+
+        let ids = |deeds: Vec<SerializableDeed>| deeds.iter().map(|deed| deed.id).collect::<Vec<_>>();
+        assert_eq!(ids(contract.deeds_by_category("environment".into(), None, None, None)), vec![0, 2]);
+        assert_eq!(ids(contract.deeds_by_category("environment".into(), Some(1), Some(1), None)), vec![2]);
+        assert_eq!(ids(contract.deeds_by_tag("CITY".into(), None, None, None)), vec![0, 1]);
+        assert_eq!(contract.internal_get_deed(0).tags, vec!["trees".to_string(), "city".to_string()]);
+
+        contract.retract_deed(0);
+        assert_eq!(ids(contract.deeds_by_category("environment".into(), None, None, None)), vec![2]);
+        assert!(contract.deeds_by_tag("trees".into(), None, None, None).is_empty());
+    }
+
+    #[test]
+    fn test_update_moves_deed_between_indexes() {
+        let mut context = get_context(accounts(2));
+        let mut contract = setup(&mut context);
+        add_deed(&mut contract, "environment", &["trees"]);
+        contract.update_deed(0, None, None, None, Some("education".into()), Some(vec!["school".into()]));
+
+        assert!(contract.deeds_by_category("environment".into(), None, None, None).is_empty());
+        assert_eq!(contract.deeds_by_category("education".into(), None, None, None).len(), 1);
+        assert!(contract.deeds_by_tag("trees".into(), None, None, None).is_empty());
+        assert_eq!(contract.deeds_by_tag("school".into(), None, None, None).len(), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "The category charity does not exist.")]
+    fn test_category_must_exist() {
+        let mut context = get_context(accounts(2));
+        let mut contract = setup(&mut context);
+        add_deed(&mut contract, "charity", &[]);
+    }
+
+    #[test]
+    #[should_panic(expected = "A deed can have at most 5 tags.")]
+    fn test_tags_are_limited() {
+        let mut context = get_context(accounts(2));
+        let mut contract = setup(&mut context);
+        add_deed(&mut contract, "education", &["a", "b", "c", "d", "e", "f"]);
+    }
+
+    #[test]
+    #[should_panic(expected = "A category with deeds cannot be removed.")]
+    fn test_cannot_remove_used_category() {
+        let mut context = get_context(accounts(2));
+        let mut contract = setup(&mut context);
+        add_deed(&mut contract, "education", &[]);
+        testing_env!(context.predecessor_account_id(accounts(2)).build());
+        contract.remove_category("education".into());
+    }
+}
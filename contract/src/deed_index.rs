@@ -0,0 +1,110 @@
+//! Indexes of the deeds of every account.
+//!
+//! Every account has a list of the deeds it authored and a list of the deeds it credited, so a
+//! frontend can show them without fetching all deeds. Authored deeds are listed in the order they
+//! were posted. Credited deeds are listed in the order they were credited, but a withdrawn credit
+//! moves the last credited deed into its place.
+
+use crate::*;
+
+pub(crate) type DeedIndex = LookupMap<AccountId, Vector<u64>>;
+
+/// Appends `deed_id` to the authored deeds of `author`.
+pub(crate) fn index_authored_deed(index: &mut DeedIndex, author: &AccountId, deed_id: u64) {
+    let mut deeds = index.get(author)
+        .unwrap_or_else(|| Vector::new(StorageKey::DeedsOfAuthor { account_id: author.clone() }));
+    deeds.push(&deed_id);
+    index.insert(author, &deeds);
+}
+
+/// Appends `deed_id` to the credited deeds of `creditor`.
+pub(crate) fn index_credited_deed(index: &mut DeedIndex, creditor: &AccountId, deed_id: u64) {
+    let mut deeds = index.get(creditor)
+        .unwrap_or_else(|| Vector::new(StorageKey::DeedsOfCreditor { account_id: creditor.clone() }));
+    deeds.push(&deed_id);
+    index.insert(creditor, &deeds);
+}
+
+/// Removes `deed_id` from the credited deeds of `creditor`.
+pub(crate) fn unindex_credited_deed(index: &mut DeedIndex, creditor: &AccountId, deed_id: u64) {
+    if let Some(mut deeds) = index.get(creditor) {
+        // Recent credits are the most likely to be withdrawn, so search from the end.
+        if let Some(position) = (0..deeds.len()).rev().find(|position| deeds.get(*position) == Some(deed_id)) {
+            deeds.swap_remove(position);
+            index.insert(creditor, &deeds);
+        }
+    }
+}
+
+impl Contract {
+    fn deeds_of_account(&self, index: &DeedIndex, account_id: &AccountId, creditor_id: Option<&AccountId>,
+                        from_index: Option<u64>, limit: Option<u64>) -> Vec<SerializableDeed> {
+        let start_index = from_index.unwrap_or_default();
+        let limit = limit.unwrap_or(u64::MAX);
+        require!(limit != 0, "Cannot provide limit of 0.");
+        let ids = match index.get(account_id) {
+            Some(ids) => ids,
+            None => return Vec::new(),
+        };
+        (start_index..ids.len())
+            .take(limit as usize)
+            .map(|index| SerializableDeed::new(self.internal_get_deed(ids.get(index).unwrap()), creditor_id))
+            .collect()
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Returns the deeds posted by `author_id`. `is_creditor` refers to `creditor_id` if it is
+    /// given.
+    pub fn deeds_by_author(&self, author_id: AccountId, from_index: Option<u64>, limit: Option<u64>,
+                           creditor_id: Option<AccountId>) -> Vec<SerializableDeed> {
+        self.deeds_of_account(&self.authored_deeds, &author_id, creditor_id.as_ref(), from_index, limit)
+    }
+
+    /// Returns the deeds credited by `creditor_id`.
+    pub fn deeds_credited_by(&self, creditor_id: AccountId, from_index: Option<u64>, limit: Option<u64>) -> Vec<SerializableDeed> {
+        self.deeds_of_account(&self.credited_deeds, &creditor_id, Some(&creditor_id), from_index, limit)
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_sdk::test_utils::accounts;
+    use near_sdk::testing_env;
+
+    use crate::tests::{call, get_context, SAFE_STORAGE_COST, TOTAL_SUPPLY};
+    use super::*;
+
+    fn ids(deeds: Vec<SerializableDeed>) -> Vec<u64> {
+        deeds.iter().map(|deed| deed.id).collect()
+    }
+
+    #[test]
+    fn test_deeds_by_author_and_creditor() {
+        let mut context = get_context(accounts(2));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into());
+        for account_id in [accounts(1), accounts(3), accounts(1)].iter() {
+            call(&mut context, account_id.clone(), SAFE_STORAGE_COST);
+            if !contract.token.accounts.contains_key(account_id) {
+                contract.storage_deposit(None, None);
+            }
+            contract.add_deed(account_id.clone(), "title".into(), "description".into(), "proof".into(), None, None);
+        }
+        call(&mut context, accounts(4), SAFE_STORAGE_COST);
+        contract.credit(2);
+        contract.credit(1);
+        contract.credit(0);
+
+        assert_eq!(ids(contract.deeds_by_author(accounts(1), None, None, None)), vec![0, 2]);
+        assert_eq!(ids(contract.deeds_by_author(accounts(1), Some(1), Some(5), None)), vec![2]);
+        assert!(contract.deeds_by_author(accounts(5), None, None, None).is_empty());
+        let credited = contract.deeds_credited_by(accounts(4), None, None);
+        assert_eq!(ids(credited), vec![2, 1, 0]);
+
+        contract.uncredit(2);
+        assert_eq!(ids(contract.deeds_credited_by(accounts(4), None, None)), vec![0, 1]);
+        assert!(contract.deeds_credited_by(accounts(4), None, None).iter().all(|deed| deed.is_creditor));
+    }
+}
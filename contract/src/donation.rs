@@ -0,0 +1,387 @@
+//! Pull based donations.
+//!
+//! A donation does not transfer anything to the DEED holders directly. Instead it increases a
+//! global reward-per-DEED index, so `donate` costs the same no matter how many authors exist.
+//! Every account remembers the index it was last settled at; the difference multiplied with its
+//! DEED balance is what it accrued since then. Accounts withdraw their accrued NEAR with
+//! `claim_donations`.
+//!
+//! Donations to a single deed or author with `donate_to_deed` and `donate_to_author` bypass the
+//! index and are added to the claimable amount of the author right away.
+//!
+//! Because the accrued amount depends on the DEED balance, an account has to be settled right
+//! before its balance changes.
+//!
+//! Rounding: the index is kept in yoctoNEAR per DEED. DEED has no decimals, so the accrual of an
+//! account is always an exact multiple of the index and needs no rounding. The only division is
+//! the one of a donation by the supply, which is rounded down. The remainder is kept as dust in
+//! `undistributed_donations` and added to the next donation, so the contract never pays out more
+//! than it received and never loses a yoctoNEAR.
+
+use crate::*;
+use crate::pause::Feature;
+
+#[derive(BorshDeserialize, BorshSerialize, Default)]
+pub struct DonationAccount {
+    /// The value of the reward-per-DEED index at the last settlement.
+    pub(crate) reward_per_token_paid: u128,
+    /// Donations accrued until the last settlement, but not yet claimed.
+    pub(crate) claimable: Balance,
+}
+
+impl Contract {
+    /// DEED balance that takes part in donations. The supply held by the owner is excluded.
+    pub(crate) fn donatable_supply(&self) -> Balance {
+        self.token.total_supply - self.token.ft_balance_of(self.owner.clone()).0
+    }
+
+    fn pending_donations(&self, account_id: &AccountId, account: &DonationAccount) -> Balance {
+        let balance: Balance = self.token.ft_balance_of(account_id.clone()).into();
+        balance * (self.reward_per_token - account.reward_per_token_paid)
+    }
+
+    /// Distributes `amount` together with the dust of earlier distributions over `supply` DEED by
+    /// increasing the reward-per-DEED index.
+    pub(crate) fn internal_distribute_donation(&mut self, amount: Balance, supply: Balance) {
+        let total = amount + self.undistributed_donations;
+        let increment = total / supply;
+        self.reward_per_token += increment;
+        self.undistributed_donations = total - increment * supply;
+    }
+
+    /// Moves the donations accrued by the current DEED balance of `account_id` into its claimable
+    /// amount. Must be called before the DEED balance of the account changes.
+    pub(crate) fn internal_settle_donations(&mut self, account_id: &AccountId) {
+        if account_id == &self.owner {
+            return;
+        }
+        let mut account = self.donation_accounts.get(account_id).unwrap_or_default();
+        account.claimable += self.pending_donations(account_id, &account);
+        account.reward_per_token_paid = self.reward_per_token;
+        self.donation_accounts.insert(account_id, &account);
+        self.internal_settle_token_donations(account_id);
+    }
+
+    /// Starts the accrual of `account_id` at the current index without accruing anything before.
+    /// Used for the previous owner, whose balance did not take part in earlier donations.
+    pub(crate) fn internal_skip_donations(&mut self, account_id: &AccountId) {
+        let mut account = self.donation_accounts.get(account_id).unwrap_or_default();
+        account.reward_per_token_paid = self.reward_per_token;
+        self.donation_accounts.insert(account_id, &account);
+        self.internal_skip_token_donations(account_id);
+    }
+
+    /// Distributes `amount` donated by `donor` to all other DEED holders. Returns `false` without
+    /// distributing anything if nobody else holds DEED.
+    pub(crate) fn internal_donate(&mut self, donor: &AccountId, amount: Balance) -> bool {
+        // The supply of the owner is not part of the donatable supply anyway.
+        let donor_balance: Balance = if donor == &self.owner { 0 } else { self.token.ft_balance_of(donor.clone()).into() };
+        let supply = self.donatable_supply() - donor_balance;
+        if supply == 0 {
+            return false;
+        }
+        if donor_balance > 0 {
+            self.internal_settle_donations(donor);
+        }
+
+        self.internal_distribute_donation(amount, supply);
+        if let Some(mut account) = self.donation_accounts.get(donor) {
+            // Skip the share of the own donation.
+            account.reward_per_token_paid = self.reward_per_token;
+            self.donation_accounts.insert(donor, &account);
+        }
+        events::DonationReceived { donor, amount: &amount.into() }.emit();
+        true
+    }
+
+    /// Settles `account_id` and makes sure it has a donation account, so that adding a direct
+    /// donation to it does not use any more storage.
+    fn internal_open_donation_account(&mut self, account_id: &AccountId) {
+        self.internal_settle_donations(account_id);
+        if !self.donation_accounts.contains_key(account_id) {
+            self.donation_accounts.insert(account_id, &DonationAccount::default());
+        }
+    }
+
+    /// Adds `amount` to the claimable donations of `account_id`, which must have been opened
+    /// with `internal_open_donation_account`.
+    fn internal_add_direct_donation(&mut self, account_id: &AccountId, amount: Balance) {
+        let mut account = self.donation_accounts.get(account_id).unwrap();
+        account.claimable += amount;
+        self.donation_accounts.insert(account_id, &account);
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Donates the attached deposit (minus the storage costs) to all DEED holders proportional to
+    /// their DEED balance. The donor is not part of the distribution of its own donation.
+    #[payable]
+    pub fn donate(
+        &mut self
+    )
+    {
+        let initial_storage_usage = env::storage_usage();
+        self.assert_not_paused(Feature::Donations);
+        let donor = env::predecessor_account_id();
+        let donation_id = self.internal_record_donation(&donor, None);
+
+        let remaining = calculate_and_check_deposit(env::storage_usage() - initial_storage_usage);
+        require!(self.internal_donate(&donor, remaining), "There is nobody with reputation to donate to.");
+        self.internal_set_donation_amount(donation_id, remaining);
+    }
+
+    /// Donates the attached deposit (minus the storage costs) to the author of the deed `id`. The
+    /// donation can be claimed with `claim_donations` and is added to the donations of the deed.
+    #[payable]
+    pub fn donate_to_deed(&mut self, id: u64) {
+        let initial_storage_usage = env::storage_usage();
+        self.assert_not_paused(Feature::Donations);
+        let donor = env::predecessor_account_id();
+        let deed = self.internal_get_deed(id);
+        deed.assert_active();
+        let author = deed.author.clone();
+        require!(donor != author, "Authors cannot donate to their own deeds.");
+
+        // Deeds of the first version use more storage once they are saved again.
+        self.internal_save_deed(deed);
+        self.internal_open_donation_account(&author);
+        let donation_id = self.internal_record_donation(&donor, None);
+        let amount = calculate_and_check_deposit(env::storage_usage() - initial_storage_usage);
+        require!(amount > 0, "The deposit does not cover more than the storage costs.");
+        self.internal_add_direct_donation(&author, amount);
+        self.internal_set_donation_amount(donation_id, amount);
+        let mut deed = self.internal_get_deed(id);
+        deed.donated += amount;
+        self.internal_save_deed(deed);
+        events::DirectDonationReceived { donor: &donor, account_id: &author, deed_id: Some(id), amount: &amount.into() }.emit();
+    }
+
+    /// Donates the attached deposit (minus the storage costs) to `account_id`, which must have
+    /// posted a deed. The donation can be claimed with `claim_donations`.
+    #[payable]
+    pub fn donate_to_author(&mut self, account_id: AccountId) {
+        let initial_storage_usage = env::storage_usage();
+        self.assert_not_paused(Feature::Donations);
+        let donor = env::predecessor_account_id();
+        require!(donor != account_id, "Authors cannot donate to themselves.");
+        require!(self.authored_deeds.contains_key(&account_id), "The account has not posted any deeds.");
+
+        self.internal_open_donation_account(&account_id);
+        let donation_id = self.internal_record_donation(&donor, None);
+        let amount = calculate_and_check_deposit(env::storage_usage() - initial_storage_usage);
+        require!(amount > 0, "The deposit does not cover more than the storage costs.");
+        self.internal_add_direct_donation(&account_id, amount);
+        self.internal_set_donation_amount(donation_id, amount);
+        events::DirectDonationReceived { donor: &donor, account_id: &account_id, deed_id: None, amount: &amount.into() }.emit();
+    }
+
+    /// Transfers all donations accrued by the caller to the caller.
+    pub fn claim_donations(&mut self) -> U128 {
+        self.assert_not_paused(Feature::Donations);
+        let account_id = env::predecessor_account_id();
+        let amount: Balance = self.claimable_donations(account_id.clone()).into();
+        require!(amount > 0, "There are no donations to claim.");
+        self.internal_settle_donations(&account_id);
+        let mut account = self.donation_accounts.get(&account_id).unwrap();
+        account.claimable = 0;
+        self.donation_accounts.insert(&account_id, &account);
+
+        events::DonationPaid { account_id: &account_id, amount: &amount.into() }.emit();
+        Promise::new(account_id).transfer(amount);
+        amount.into()
+    }
+
+    /// Returns the yoctoNEAR that were left over by rounding and will be part of the next donation.
+    pub fn undistributed_donations(&self) -> U128 {
+        self.undistributed_donations.into()
+    }
+
+    /// Returns the amount of yoctoNEAR `account_id` can currently claim. The owner can only claim
+    /// what it accrued before it became the owner.
+    pub fn claimable_donations(&self, account_id: AccountId) -> U128 {
+        match self.donation_accounts.get(&account_id) {
+            Some(account) if account_id == self.owner => account.claimable.into(),
+            Some(account) => (account.claimable + self.pending_donations(&account_id, &account)).into(),
+            None => 0.into(),
+        }
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::testing_env;
+
+    use crate::tests::{call, get_context, SAFE_STORAGE_COST, TOTAL_SUPPLY};
+    use super::*;
+
+    /// Registers `account_id` and lets it post a deed.
+    fn register_and_add_deed(context: &mut VMContextBuilder, contract: &mut Contract, account_id: AccountId) {
+        call(context, account_id.clone(), SAFE_STORAGE_COST);
+        contract.storage_deposit(None, None);
+        contract.add_deed(account_id, "title".into(), "description".into(), "proof".into(), None, None);
+    }
+
+    fn donate(context: &mut VMContextBuilder, contract: &mut Contract, donor: AccountId, amount: Balance) {
+        call(context, donor, amount);
+        contract.donate();
+    }
+
+    #[test]
+    fn test_claim_donations_resets_claimable_amount() {
+        let mut context = get_context(accounts(2));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into());
+        register_and_add_deed(&mut context, &mut contract, accounts(1));
+        register_and_add_deed(&mut context, &mut contract, accounts(3));
+        contract.credit(0);
+
+        donate(&mut context, &mut contract, accounts(4), 10u128.pow(24));
+        let claimable = contract.claimable_donations(accounts(1)).0;
+        assert!(claimable > 0);
+
+        call(&mut context, accounts(1), 0);
+        assert_eq!(contract.claim_donations().0, claimable);
+        assert_eq!(contract.claimable_donations(accounts(1)).0, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "There are no donations to claim.")]
+    fn test_cannot_claim_without_donations() {
+        let mut context = get_context(accounts(2));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into());
+        register_and_add_deed(&mut context, &mut contract, accounts(1));
+
+        contract.claim_donations();
+    }
+
+    #[test]
+    fn test_later_reputation_does_not_earn_earlier_donations() {
+        let mut context = get_context(accounts(2));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into());
+        register_and_add_deed(&mut context, &mut contract, accounts(1));
+        register_and_add_deed(&mut context, &mut contract, accounts(3));
+        contract.credit(0);
+
+        donate(&mut context, &mut contract, accounts(4), 10u128.pow(24));
+        let claimable = contract.claimable_donations(accounts(1)).0;
+
+        call(&mut context, accounts(1), SAFE_STORAGE_COST);
+        contract.credit(1);
+
+        assert_eq!(contract.claimable_donations(accounts(1)).0, claimable);
+        assert_eq!(contract.claimable_donations(accounts(3)).0, 0);
+    }
+
+    #[test]
+    fn test_distributions_never_exceed_donations() {
+        let mut context = get_context(accounts(2));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into());
+        register_and_add_deed(&mut context, &mut contract, accounts(1));
+        register_and_add_deed(&mut context, &mut contract, accounts(3));
+        contract.credit(0);
+        register_and_add_deed(&mut context, &mut contract, accounts(5));
+        contract.credit(0);
+        contract.credit(1);
+
+        let supply = contract.donatable_supply();
+        assert_eq!(supply, 3);
+        let donations: [Balance; 5] = [10, 7, 1, 2, 999_999_999_999_999_999_999_999_999];
+        let mut donated = 0;
+        for amount in donations {
+            contract.internal_distribute_donation(amount, supply);
+            donated += amount;
+
+            let paid: Balance = [accounts(1), accounts(3), accounts(5)]
+                .iter()
+                .map(|account_id| contract.claimable_donations(account_id.clone()).0)
+                .sum();
+            assert!(paid <= donated, "Paid out {} of {} donated yoctoNEAR.", paid, donated);
+            assert_eq!(paid + contract.undistributed_donations().0, donated);
+        }
+    }
+
+    #[test]
+    fn test_dust_is_part_of_the_next_donation() {
+        let mut context = get_context(accounts(2));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into());
+        register_and_add_deed(&mut context, &mut contract, accounts(1));
+        register_and_add_deed(&mut context, &mut contract, accounts(3));
+        contract.credit(0);
+        register_and_add_deed(&mut context, &mut contract, accounts(5));
+        contract.credit(1);
+        call(&mut context, accounts(1), SAFE_STORAGE_COST);
+        contract.credit(2);
+
+        contract.internal_distribute_donation(11, 3);
+        assert_eq!(contract.undistributed_donations().0, 2);
+        assert_eq!(contract.claimable_donations(accounts(1)).0, 3);
+
+        contract.internal_distribute_donation(1, 3);
+        assert_eq!(contract.undistributed_donations().0, 0);
+        assert_eq!(contract.claimable_donations(accounts(1)).0, 4);
+        assert_eq!(contract.claimable_donations(accounts(3)).0, 4);
+        assert_eq!(contract.claimable_donations(accounts(5)).0, 4);
+    }
+
+    #[test]
+    fn test_donor_does_not_receive_own_donation() {
+        let mut context = get_context(accounts(2));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into());
+        register_and_add_deed(&mut context, &mut contract, accounts(1));
+        register_and_add_deed(&mut context, &mut contract, accounts(3));
+        contract.credit(0);
+        call(&mut context, accounts(1), SAFE_STORAGE_COST);
+        contract.credit(1);
+
+        donate(&mut context, &mut contract, accounts(3), 10u128.pow(24));
+
+        assert_eq!(contract.claimable_donations(accounts(3)).0, 0);
+        assert!(contract.claimable_donations(accounts(1)).0 > 0);
+    }
+
+    #[test]
+    fn test_donation_to_deed_goes_to_its_author() {
+        let mut context = get_context(accounts(2));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into());
+        register_and_add_deed(&mut context, &mut contract, accounts(1));
+        register_and_add_deed(&mut context, &mut contract, accounts(3));
+        contract.credit(0);
+
+        call(&mut context, accounts(4), 10u128.pow(24));
+        contract.donate_to_deed(1);
+        let claimable = contract.claimable_donations(accounts(3)).0;
+        assert!(claimable > 0 && claimable <= 10u128.pow(24));
+        assert_eq!(contract.claimable_donations(accounts(1)).0, 0);
+        assert_eq!(contract.internal_get_deed(1).donated, claimable);
+
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(10u128.pow(24))
+            .build());
+        contract.donate_to_author(accounts(3));
+        let donated = contract.total_donated_by(accounts(4)).0 - claimable;
+        assert!(donated > 0 && donated < 10u128.pow(24));
+        assert_eq!(contract.claimable_donations(accounts(3)).0, claimable + donated);
+        assert_eq!(contract.internal_get_deed(1).donated, claimable);
+    }
+
+    #[test]
+    #[should_panic(expected = "Authors cannot donate to their own deeds.")]
+    fn test_author_cannot_donate_to_own_deed() {
+        let mut context = get_context(accounts(2));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into());
+        register_and_add_deed(&mut context, &mut contract, accounts(1));
+
+        testing_env!(context.attached_deposit(10u128.pow(24)).build());
+        contract.donate_to_deed(0);
+    }
+}
@@ -0,0 +1,247 @@
+//! Donation history.
+//!
+//! Every donation is recorded, no matter whether it was split between all DEED holders, made to a
+//! single deed or author, paid in a fungible token or released by a schedule. Donations in NEAR
+//! also add up to the total of their donor. The donors with the highest totals are kept in a short
+//! leaderboard that is updated with every donation, so reading it does not depend on the number
+//! of donors.
+//!
+//! Donations in fungible tokens and the installments of schedules are recorded in calls that
+//! attach no deposit. Their records are paid from the record storage the donor deposited with
+//! `deposit_record_storage`; schedules deposit it for all of their installments up front. A
+//! donation whose record is not covered is still made, but not recorded.
+
+use crate::*;
+use crate::pause::Feature;
+use near_sdk::json_types::U64;
+
+/// Number of donors on the leaderboard.
+pub const MAX_TOP_DONORS: usize = 10;
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct Donation {
+    pub(crate) donor: AccountId,
+    pub(crate) amount: Balance,
+    /// The fungible token that was donated, `None` for NEAR.
+    pub(crate) token_id: Option<AccountId>,
+    /// Block timestamp in nanoseconds at which the donation was made.
+    pub(crate) timestamp: u64
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct SerializableDonation {
+    id: u64,
+    donor: AccountId,
+    amount: U128,
+    token_id: Option<AccountId>,
+    timestamp: U64
+}
+
+impl Contract {
+    /// Records a donation of `donor` without an amount. The amount is set with
+    /// `internal_set_donation_amount`, which uses no more storage, once the storage costs of the
+    /// record were taken from the deposit.
+    pub(crate) fn internal_record_donation(&mut self, donor: &AccountId, token_id: Option<AccountId>) -> u64 {
+        let id = self.donations.len();
+        if token_id.is_none() && !self.donor_totals.contains_key(donor) {
+            self.donor_totals.insert(donor, &0);
+        }
+        self.donations.push(&Donation {
+            donor: donor.clone(),
+            amount: 0,
+            token_id,
+            timestamp: env::block_timestamp()
+        });
+        id
+    }
+
+    pub(crate) fn internal_set_donation_amount(&mut self, id: u64, amount: Balance) {
+        let mut donation = self.donations.get(id).unwrap();
+        donation.amount = amount;
+        self.donations.replace(id, &donation);
+        if donation.token_id.is_none() {
+            let total = self.donor_totals.get(&donation.donor).unwrap_or_default() + amount;
+            self.donor_totals.insert(&donation.donor, &total);
+            self.internal_update_top_donors(&donation.donor, total);
+        }
+    }
+
+    /// Returns what the storage of a donation record of `donor` costs.
+    pub(crate) fn donation_record_cost(&mut self, donor: &AccountId, token_id: Option<AccountId>) -> Balance {
+        let initial_storage_usage = env::storage_usage();
+        self.donations.push(&Donation { donor: donor.clone(), amount: 0, token_id, timestamp: 0 });
+        let cost = Balance::from(env::storage_usage() - initial_storage_usage) * env::storage_byte_cost();
+        self.donations.pop_raw();
+        cost
+    }
+
+    /// Records a donation of `donor` in a call without deposit. The storage of the record is
+    /// taken from the record storage of the donor. Returns `false` without recording anything if
+    /// the record storage does not cover it.
+    pub(crate) fn internal_add_prepaid_donation(&mut self, donor: &AccountId, amount: Balance, token_id: Option<AccountId>) -> bool {
+        let initial_storage_usage = env::storage_usage();
+        let id = self.internal_record_donation(donor, token_id);
+        let cost = Balance::from(env::storage_usage() - initial_storage_usage) * env::storage_byte_cost();
+        let record_storage = self.record_storage.get(donor).unwrap_or_default();
+        if record_storage < cost {
+            self.donations.pop_raw();
+            log!("The donation of {} was not recorded, its storage is not covered by deposit_record_storage.", donor);
+            return false;
+        }
+        self.record_storage.insert(donor, &(record_storage - cost));
+        self.internal_set_donation_amount(id, amount);
+        true
+    }
+
+    /// Adds `amount` to the record storage of `account_id`. The entry has to exist already.
+    pub(crate) fn internal_add_record_storage(&mut self, account_id: &AccountId, amount: Balance) {
+        let record_storage = self.record_storage.get(account_id).unwrap() + amount;
+        self.record_storage.insert(account_id, &record_storage);
+    }
+
+    fn internal_update_top_donors(&mut self, donor: &AccountId, total: Balance) {
+        self.top_donors.retain(|(account_id, _)| account_id != donor);
+        let index = self.top_donors
+            .iter()
+            .position(|(_, other_total)| *other_total < total)
+            .unwrap_or(self.top_donors.len());
+        self.top_donors.insert(index, (donor.clone(), total));
+        self.top_donors.truncate(MAX_TOP_DONORS);
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    pub fn get_donations_count(&self) -> u64 {
+        self.donations.len()
+    }
+
+    /// Returns the donations in the order they were made.
+    pub fn donations(&self, from_index: Option<u64>, limit: Option<u64>) -> Vec<SerializableDonation> {
+        let start_index = from_index.unwrap_or_default();
+        let limit = limit.unwrap_or(u64::MAX);
+        require!(limit != 0, "Cannot provide limit of 0.");
+        (start_index..self.donations.len())
+            .take(limit as usize)
+            .map(|id| {
+                let donation = self.donations.get(id).unwrap();
+                SerializableDonation {
+                    id,
+                    donor: donation.donor,
+                    amount: donation.amount.into(),
+                    token_id: donation.token_id,
+                    timestamp: donation.timestamp.into()
+                }
+            })
+            .collect()
+    }
+
+    /// Returns the yoctoNEAR `donor` donated in total. Donations of fungible tokens are not part
+    /// of the total.
+    pub fn total_donated_by(&self, donor: AccountId) -> U128 {
+        self.donor_totals.get(&donor).unwrap_or_default().into()
+    }
+
+    /// Deposits the attached deposit minus the storage costs for the records of future donations
+    /// of the caller that attach no deposit, like donations in fungible tokens. Returns the record
+    /// storage of the caller.
+    #[payable]
+    pub fn deposit_record_storage(&mut self) -> U128 {
+        let initial_storage_usage = env::storage_usage();
+
+        self.assert_not_paused(Feature::Donations);
+        let account_id = env::predecessor_account_id();
+        let record_storage = self.record_storage.get(&account_id).unwrap_or_default();
+        self.record_storage.insert(&account_id, &record_storage);
+        let amount = calculate_and_check_deposit(env::storage_usage() - initial_storage_usage);
+        self.internal_add_record_storage(&account_id, amount);
+        (record_storage + amount).into()
+    }
+
+    /// Transfers the unused record storage of the caller back to the caller. Returns the
+    /// transferred amount.
+    pub fn withdraw_record_storage(&mut self) -> U128 {
+        self.assert_not_paused(Feature::Donations);
+        let account_id = env::predecessor_account_id();
+        let record_storage = self.record_storage.remove(&account_id).unwrap_or_default();
+        require!(record_storage > 0, "There is no record storage to withdraw.");
+        Promise::new(account_id).transfer(record_storage);
+        record_storage.into()
+    }
+
+    /// Returns the yoctoNEAR `account_id` deposited for the storage of donation records.
+    pub fn record_storage_of(&self, account_id: AccountId) -> U128 {
+        self.record_storage.get(&account_id).unwrap_or_default().into()
+    }
+
+    /// Returns up to `MAX_TOP_DONORS` donors with the highest totals in NEAR, highest first.
+    pub fn top_donors(&self) -> Vec<(AccountId, U128)> {
+        self.top_donors
+            .iter()
+            .map(|(donor, total)| (donor.clone(), U128(*total)))
+            .collect()
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::testing_env;
+
+    use crate::tests::{call, get_context, ONE_NEAR, SAFE_STORAGE_COST, TOTAL_SUPPLY};
+    use super::*;
+
+    fn donate(context: &mut VMContextBuilder, contract: &mut Contract, donor: AccountId, amount: Balance) {
+        call(context, donor, amount);
+        contract.donate();
+    }
+
+    #[test]
+    fn test_donations_are_recorded_apart_from_deeds() {
+        let mut context = get_context(accounts(2));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into());
+        call(&mut context, accounts(1), SAFE_STORAGE_COST);
+        contract.storage_deposit(None, None);
+        contract.add_deed(accounts(1), "title".into(), "description".into(), "proof".into(), None, None);
+        testing_env!(context.predecessor_account_id(accounts(3)).build());
+        contract.credit(0);
+
+        donate(&mut context, &mut contract, accounts(4), ONE_NEAR);
+        donate(&mut context, &mut contract, accounts(5), 3 * ONE_NEAR);
+        donate(&mut context, &mut contract, accounts(4), 3 * ONE_NEAR);
+
+        assert_eq!(contract.deeds.len(), 1);
+        let donations = contract.donations(Some(1), None);
+        assert_eq!(donations.len(), 2);
+        assert_eq!(donations[0].donor, accounts(5));
+        assert_eq!(donations[0].token_id, None);
+        assert_eq!(contract.claimable_donations(accounts(1)).0, contract.donations(None, None).iter().map(|donation| donation.amount.0).sum());
+
+        let total = contract.total_donated_by(accounts(4)).0;
+        assert!(total > donations[0].amount.0);
+        assert_eq!(contract.top_donors(), vec![(accounts(4), total.into()), (accounts(5), donations[0].amount)]);
+    }
+
+    #[test]
+    fn test_leaderboard_keeps_top_donors() {
+        let context = get_context(accounts(2));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into());
+        let mut add_donation = |donor: AccountId, amount: Balance| {
+            let id = contract.internal_record_donation(&donor, None);
+            contract.internal_set_donation_amount(id, amount);
+        };
+        for amount in 1..=MAX_TOP_DONORS as u128 + 2 {
+            add_donation(format!("donor{}.near", amount).parse().unwrap(), amount);
+        }
+        add_donation("donor1.near".parse().unwrap(), 100);
+
+        let top_donors = contract.top_donors();
+        assert_eq!(top_donors.len(), MAX_TOP_DONORS);
+        assert_eq!(top_donors[0], ("donor1.near".parse().unwrap(), 101.into()));
+        assert_eq!(top_donors[1].1 .0, MAX_TOP_DONORS as u128 + 2);
+        assert_eq!(top_donors[MAX_TOP_DONORS - 1].1 .0, 4);
+    }
+}
@@ -0,0 +1,304 @@
+//! Recurring donations.
+//!
+//! A donor deposits the budget of a schedule up front: `periods` installments of
+//! `amount_per_period`, one due at the end of every period. Anybody can call
+//! `process_due_donations` to release the installments that are due into the distribution of
+//! `donate`. The donor can cancel a schedule at any time; installments that are already due are
+//! released first and the rest of the budget is refunded.
+//!
+//! The deposit also covers the records of all installments in the donation history. It is added
+//! to the record storage of the donor, which can withdraw what is left with
+//! `withdraw_record_storage`.
+
+use crate::*;
+use crate::pause::Feature;
+use near_sdk::json_types::U64;
+
+/// Number of schedules `process_due_donations` looks at if no limit is given.
+const DEFAULT_PROCESS_LIMIT: u64 = 50;
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct DonationSchedule {
+    id: u64,
+    donor: AccountId,
+    amount_per_period: Balance,
+    /// Length of a period in nanoseconds.
+    period: u64,
+    periods: u64,
+    /// Number of installments that were released.
+    released: u64,
+    /// Block timestamp in nanoseconds at which the schedule was created.
+    started_at: u64,
+    cancelled: bool
+}
+
+impl DonationSchedule {
+    /// Number of installments that are due but were not released yet.
+    fn due_installments(&self) -> u64 {
+        if self.cancelled {
+            return 0;
+        }
+        let elapsed = (env::block_timestamp() - self.started_at) / self.period;
+        elapsed.min(self.periods) - self.released
+    }
+
+    fn is_finished(&self) -> bool {
+        self.cancelled || self.released == self.periods
+    }
+
+    fn remaining_budget(&self) -> Balance {
+        self.amount_per_period * Balance::from(self.periods - self.released)
+    }
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct SerializableDonationSchedule {
+    id: u64,
+    donor: AccountId,
+    amount_per_period: U128,
+    period: U64,
+    periods: u64,
+    released: u64,
+    started_at: U64,
+    cancelled: bool,
+    /// Block timestamp at which the next installment is due, if any.
+    next_due_at: Option<U64>
+}
+
+impl From<&DonationSchedule> for SerializableDonationSchedule {
+    fn from(schedule: &DonationSchedule) -> Self {
+        Self {
+            id: schedule.id,
+            donor: schedule.donor.clone(),
+            amount_per_period: schedule.amount_per_period.into(),
+            period: schedule.period.into(),
+            periods: schedule.periods,
+            released: schedule.released,
+            started_at: schedule.started_at.into(),
+            cancelled: schedule.cancelled,
+            next_due_at: (!schedule.is_finished())
+                .then(|| (schedule.started_at + (schedule.released + 1) * schedule.period).into())
+        }
+    }
+}
+
+impl Contract {
+    fn internal_get_donation_schedule(&self, schedule_id: u64) -> DonationSchedule {
+        assert!(self.donation_schedules.len() > schedule_id, "The schedule id is out of range.");
+        self.donation_schedules.get(schedule_id).unwrap()
+    }
+
+    /// Releases the installments of `schedule` that are due. Returns `false` if there were due
+    /// installments, but nobody to donate them to.
+    fn internal_release_due_donations(&mut self, schedule: &mut DonationSchedule) -> bool {
+        let due = schedule.due_installments();
+        if due == 0 {
+            return true;
+        }
+        let amount = schedule.amount_per_period * Balance::from(due);
+        if !self.internal_donate(&schedule.donor, amount) {
+            return false;
+        }
+        self.internal_add_prepaid_donation(&schedule.donor, amount, None);
+        schedule.released += due;
+        if schedule.is_finished() {
+            self.active_donation_schedules.remove(&schedule.id);
+        }
+        self.donation_schedules.replace(schedule.id, schedule);
+        true
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Schedules `periods` donations of `amount_per_period` yoctoNEAR to all DEED holders, one at
+    /// the end of every `period` nanoseconds. The attached deposit has to cover the whole budget,
+    /// the storage costs and the records of the installments, anything above is refunded.
+    #[payable]
+    pub fn create_donation_schedule(
+        &mut self,
+        amount_per_period: U128,
+        period: U64,
+        periods: u64
+    ) -> u64
+    {
+        let initial_storage_usage = env::storage_usage();
+
+        self.assert_not_paused(Feature::Donations);
+        require!(amount_per_period.0 > 0 && periods > 0, "A schedule needs at least one installment.");
+        require!(period.0 > 0, "The period cannot be empty.");
+        let donor = env::predecessor_account_id();
+        let id = self.donation_schedules.len();
+        let schedule = DonationSchedule {
+            id,
+            donor: donor.clone(),
+            amount_per_period: amount_per_period.0,
+            period: period.0,
+            periods,
+            released: 0,
+            started_at: env::block_timestamp(),
+            cancelled: false
+        };
+        self.donation_schedules.push(&schedule);
+        self.active_donation_schedules.insert(&id);
+        if !self.donor_totals.contains_key(&donor) {
+            self.donor_totals.insert(&donor, &0);
+        }
+        if !self.record_storage.contains_key(&donor) {
+            self.record_storage.insert(&donor, &0);
+        }
+
+        let budget = schedule.remaining_budget() + Balance::from(periods) * self.donation_record_cost(&donor, None);
+        let remaining = calculate_and_check_deposit(env::storage_usage() - initial_storage_usage);
+        assert!(remaining >= budget, "Must attach {} yoctoNEAR more to cover the budget", budget - remaining);
+        self.internal_add_record_storage(&donor, budget - schedule.remaining_budget());
+        if remaining - budget > 1 {
+            Promise::new(donor.clone()).transfer(remaining - budget);
+        }
+        events::DonationScheduleCreated { schedule_id: id, donor: &donor, amount_per_period: &amount_per_period, periods }.emit();
+        id
+    }
+
+    /// Releases the due installments of up to `limit` active schedules starting at `from_index`.
+    /// Can be called by anybody. Returns the number of schedules that released installments.
+    pub fn process_due_donations(&mut self, from_index: Option<u64>, limit: Option<u64>) -> u64 {
+        self.assert_not_paused(Feature::Donations);
+        let start_index = from_index.unwrap_or_default();
+        let limit = limit.unwrap_or(DEFAULT_PROCESS_LIMIT);
+        require!(limit != 0, "Cannot provide limit of 0.");
+        let schedule_ids: Vec<u64> = {
+            let active = self.active_donation_schedules.as_vector();
+            (start_index..active.len())
+                .take(limit as usize)
+                .map(|index| active.get(index).unwrap())
+                .collect()
+        };
+        let mut processed = 0;
+        for schedule_id in schedule_ids {
+            let mut schedule = self.internal_get_donation_schedule(schedule_id);
+            if schedule.due_installments() == 0 {
+                continue;
+            }
+            if self.internal_release_due_donations(&mut schedule) {
+                processed += 1;
+            }
+        }
+        processed
+    }
+
+    /// Cancels the schedule `schedule_id` of the caller. Installments that are due are donated,
+    /// the rest of the budget is refunded to the caller. Returns the refunded amount.
+    pub fn cancel_donation_schedule(&mut self, schedule_id: u64) -> U128 {
+        self.assert_not_paused(Feature::Donations);
+        let mut schedule = self.internal_get_donation_schedule(schedule_id);
+        assert_eq!(schedule.donor, env::predecessor_account_id(), "Only the donor can cancel the schedule.");
+        require!(!schedule.is_finished(), "The schedule is already finished.");
+        // If nobody can receive the due installments, they are refunded as well.
+        self.internal_release_due_donations(&mut schedule);
+
+        let refund = schedule.remaining_budget();
+        schedule.cancelled = true;
+        self.active_donation_schedules.remove(&schedule_id);
+        self.donation_schedules.replace(schedule_id, &schedule);
+        events::DonationScheduleCancelled { schedule_id, donor: &schedule.donor, refund: &refund.into() }.emit();
+        if refund > 0 {
+            Promise::new(schedule.donor).transfer(refund);
+        }
+        refund.into()
+    }
+
+    pub fn get_donation_schedule(&self, schedule_id: u64) -> SerializableDonationSchedule {
+        (&self.internal_get_donation_schedule(schedule_id)).into()
+    }
+
+    /// Returns the schedules that still have installments to release.
+    pub fn active_donation_schedules(&self, from_index: Option<u64>, limit: Option<u64>) -> Vec<SerializableDonationSchedule> {
+        let start_index = from_index.unwrap_or_default();
+        let limit = limit.unwrap_or(u64::MAX);
+        require!(limit != 0, "Cannot provide limit of 0.");
+        let active = self.active_donation_schedules.as_vector();
+        (start_index..active.len())
+            .take(limit as usize)
+            .map(|index| (&self.internal_get_donation_schedule(active.get(index).unwrap())).into())
+            .collect()
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::testing_env;
+
+    use crate::tests::{call, get_context, ONE_NEAR, SAFE_STORAGE_COST, TOTAL_SUPPLY};
+    use super::*;
+
+    const PERIOD: u64 = 1_000;
+
+    /// Sets up a contract where accounts(1) holds DEED and accounts(4) donates 1 NEAR in each of
+    /// three periods.
+    fn setup(context: &mut VMContextBuilder) -> Contract {
+        testing_env!(context.block_timestamp(0).build());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into());
+        for account_id in [accounts(1), accounts(3)].iter() {
+            call(context.block_timestamp(0), account_id.clone(), SAFE_STORAGE_COST);
+            contract.storage_deposit(None, None);
+            contract.add_deed(account_id.clone(), "title".into(), "description".into(), "proof".into(), None, None);
+        }
+        contract.credit(0);
+
+        call(context.block_timestamp(0), accounts(4), 3 * ONE_NEAR + SAFE_STORAGE_COST);
+        contract.create_donation_schedule(ONE_NEAR.into(), PERIOD.into(), 3);
+        contract
+    }
+
+    #[test]
+    fn test_due_installments_are_released() {
+        let mut context = get_context(accounts(2));
+        let mut contract = setup(&mut context);
+
+        call(context.block_timestamp(PERIOD - 1), accounts(5), 0);
+        assert_eq!(contract.process_due_donations(None, None), 0);
+        assert_eq!(contract.claimable_donations(accounts(1)).0, 0);
+
+        call(context.block_timestamp(2 * PERIOD), accounts(5), 0);
+        assert_eq!(contract.process_due_donations(None, None), 1);
+        assert_eq!(contract.claimable_donations(accounts(1)).0, 2 * ONE_NEAR);
+        assert_eq!(contract.get_donation_schedule(0).next_due_at, Some((3 * PERIOD).into()));
+
+        call(context.block_timestamp(10 * PERIOD), accounts(5), 0);
+        contract.process_due_donations(None, None);
+        assert_eq!(contract.claimable_donations(accounts(1)).0, 3 * ONE_NEAR);
+        assert!(contract.active_donation_schedules(None, None).is_empty());
+
+        // Two records were paid from the deposit, the third installment is left over.
+        assert_eq!(contract.get_donations_count(), 2);
+        assert_eq!(contract.total_donated_by(accounts(4)).0, 3 * ONE_NEAR);
+        assert_eq!(contract.record_storage_of(accounts(4)).0, contract.donation_record_cost(&accounts(4), None));
+    }
+
+    #[test]
+    fn test_cancel_refunds_unspent_budget() {
+        let mut context = get_context(accounts(2));
+        let mut contract = setup(&mut context);
+
+        call(context.block_timestamp(PERIOD), accounts(4), 0);
+        assert_eq!(contract.cancel_donation_schedule(0).0, 2 * ONE_NEAR);
+        assert_eq!(contract.claimable_donations(accounts(1)).0, ONE_NEAR);
+        assert!(contract.get_donation_schedule(0).cancelled);
+
+        call(context.block_timestamp(10 * PERIOD), accounts(5), 0);
+        assert_eq!(contract.process_due_donations(None, None), 0);
+        assert_eq!(contract.claimable_donations(accounts(1)).0, ONE_NEAR);
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the donor can cancel the schedule.")]
+    fn test_only_donor_cancels_schedule() {
+        let mut context = get_context(accounts(2));
+        let mut contract = setup(&mut context);
+
+        call(context.block_timestamp(PERIOD), accounts(1), 0);
+        contract.cancel_donation_schedule(0);
+    }
+}
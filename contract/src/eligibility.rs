@@ -0,0 +1,183 @@
+//! Eligibility of creditors.
+//!
+//! To make it harder to pump the reputation of an author with throwaway accounts, admins can
+//! require a minimum DEED balance and a minimum time since the registration of the creditor, or
+//! only allow verified accounts to credit. By default every account is eligible.
+//!
+//! The registration time is recorded by `storage_deposit`. Accounts that were registered before
+//! the contract recorded it count as old enough.
+
+use crate::*;
+use crate::access_control::Role;
+use near_sdk::json_types::U64;
+
+#[derive(BorshDeserialize, BorshSerialize, Deserialize, Serialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct CreditRules {
+    /// DEED a creditor must hold.
+    pub min_balance: U128,
+    /// Nanoseconds that must have passed since the registration of a creditor.
+    pub min_account_age: U64,
+    /// Whether only verified accounts can credit.
+    pub verified_only: bool
+}
+
+impl Default for CreditRules {
+    fn default() -> Self {
+        Self { min_balance: 0.into(), min_account_age: 0.into(), verified_only: false }
+    }
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct CreditEligibility {
+    eligible: bool,
+    /// Why the account cannot credit, empty if it is eligible.
+    reasons: Vec<String>
+}
+
+impl Contract {
+    /// Returns why `account_id` cannot credit deeds under the current rules.
+    fn credit_ineligibility(&self, account_id: &AccountId) -> Vec<String> {
+        let rules = &self.credit_rules;
+        let mut reasons = Vec::new();
+        let balance: Balance = self.token.ft_balance_of(account_id.clone()).into();
+        if balance < rules.min_balance.0 {
+            reasons.push(format!("The account needs at least {} DEED to credit, it holds {}.", rules.min_balance.0, balance));
+        }
+        if rules.min_account_age.0 > 0 {
+            if !self.token.accounts.contains_key(account_id) {
+                reasons.push("The account must be registered to credit.".to_string());
+            } else if let Some(registered_at) = self.registered_at.get(account_id) {
+                let age = env::block_timestamp().saturating_sub(registered_at);
+                if age < rules.min_account_age.0 {
+                    reasons.push(format!("The account must be registered for {} more nanoseconds to credit.", rules.min_account_age.0 - age));
+                }
+            }
+        }
+        if rules.verified_only && !self.verified_accounts.contains(account_id) {
+            reasons.push("Only verified accounts can credit.".to_string());
+        }
+        reasons
+    }
+
+    /// Panics if `account_id` cannot credit deeds.
+    pub(crate) fn assert_eligible_creditor(&self, account_id: &AccountId) {
+        let reasons = self.credit_ineligibility(account_id);
+        require!(reasons.is_empty(), reasons.join(" "));
+    }
+
+    /// Records when `account_id` was registered.
+    pub(crate) fn internal_record_registration(&mut self, account_id: &AccountId) {
+        self.registered_at.insert(account_id, &env::block_timestamp());
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Sets the rules creditors have to fulfill. Can only be called by admins.
+    pub fn set_credit_rules(&mut self, rules: CreditRules) {
+        self.assert_role(Role::Admin);
+        self.credit_rules = rules;
+    }
+
+    pub fn get_credit_rules(&self) -> CreditRules {
+        self.credit_rules.clone()
+    }
+
+    /// Adds `account_ids` to the verified accounts. Can only be called by admins.
+    #[payable]
+    pub fn verify_accounts(&mut self, account_ids: Vec<AccountId>) {
+        let initial_storage_usage = env::storage_usage();
+
+        self.assert_role(Role::Admin);
+        self.verified_accounts.extend(account_ids);
+
+        refund_deposit(env::storage_usage() - initial_storage_usage);
+    }
+
+    /// Removes `account_ids` from the verified accounts. Can only be called by admins.
+    pub fn unverify_accounts(&mut self, account_ids: Vec<AccountId>) {
+        self.assert_role(Role::Admin);
+        for account_id in account_ids {
+            self.verified_accounts.remove(&account_id);
+        }
+    }
+
+    pub fn is_verified(&self, account_id: AccountId) -> bool {
+        self.verified_accounts.contains(&account_id)
+    }
+
+    /// Returns whether `account_id` can currently credit deeds and why not.
+    pub fn credit_eligibility(&self, account_id: AccountId) -> CreditEligibility {
+        let reasons = self.credit_ineligibility(&account_id);
+        CreditEligibility { eligible: reasons.is_empty(), reasons }
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::testing_env;
+
+    use crate::tests::{call, get_context, SAFE_STORAGE_COST, TOTAL_SUPPLY};
+    use super::*;
+
+    const DAY: u64 = 86_400_000_000_000;
+
+    /// Sets up a contract with a deed of accounts(1), applies `rules` and registers accounts(3)
+    /// at timestamp 0.
+    fn setup(context: &mut VMContextBuilder, rules: CreditRules) -> Contract {
+        testing_env!(context.block_timestamp(0).build());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into());
+        contract.set_credit_rules(rules);
+        call(context, accounts(1), SAFE_STORAGE_COST);
+        contract.storage_deposit(None, None);
+        contract.add_deed(accounts(1), "title".into(), "description".into(), "proof".into(), None, None);
+
+        call(context, accounts(3), SAFE_STORAGE_COST);
+        contract.storage_deposit(None, None);
+        contract
+    }
+
+    #[test]
+    fn test_eligibility_explains_reasons() {
+        let mut context = get_context(accounts(2));
+        let rules = CreditRules { min_balance: 1.into(), min_account_age: DAY.into(), verified_only: true };
+        let contract = setup(&mut context, rules);
+
+        let eligibility = contract.credit_eligibility(accounts(3));
+        assert!(!eligibility.eligible);
+        assert_eq!(eligibility.reasons, vec![
+            "The account needs at least 1 DEED to credit, it holds 0.".to_string(),
+            format!("The account must be registered for {} more nanoseconds to credit.", DAY),
+            "Only verified accounts can credit.".to_string(),
+        ]);
+        assert_eq!(contract.credit_eligibility(accounts(4)).reasons[1], "The account must be registered to credit.");
+    }
+
+    #[test]
+    fn test_old_enough_account_can_credit() {
+        let mut context = get_context(accounts(2));
+        let rules = CreditRules { min_account_age: DAY.into(), ..Default::default() };
+        let mut contract = setup(&mut context, rules);
+
+        testing_env!(context.block_timestamp(DAY).build());
+        assert!(contract.credit_eligibility(accounts(3)).eligible);
+        contract.credit(0);
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "Only verified accounts can credit.")]
+    fn test_unverified_account_cannot_credit() {
+        let mut context = get_context(accounts(2));
+        let rules = CreditRules { verified_only: true, ..Default::default() };
+        let mut contract = setup(&mut context, rules);
+
+        testing_env!(context.predecessor_account_id(accounts(2)).build());
+        contract.verify_accounts(vec![accounts(4)]);
+        testing_env!(context.predecessor_account_id(accounts(3)).build());
+        contract.credit(0);
+    }
+}
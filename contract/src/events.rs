@@ -0,0 +1,512 @@
+//! Events of the social bounty contract in the events format (NEP-297):
+//! <https://github.com/near/NEPs/blob/master/specs/Standards/EventsFormat.md>
+//!
+//! Every event is logged as `EVENT_JSON:{"standard":"social_bounty","version":...,"event":...,
+//! "data":[...]}`. The version belongs to the schema of the single event and is only increased
+//! when the data of that event changes. Changes of the DEED balances are logged with the NEP-141
+//! events of `near_contract_standards` instead.
+
+use near_sdk::json_types::U128;
+use near_sdk::serde::Serialize;
+use near_sdk::{env, AccountId};
+
+use crate::access_control::Role;
+use crate::pause::Feature;
+
+const STANDARD: &str = "social_bounty";
+
+#[derive(Serialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+#[serde(tag = "event", content = "data")]
+#[serde(rename_all = "snake_case")]
+enum SocialEventKind<'a> {
+    DeedAdded(&'a [DeedAdded<'a>]),
+    DeedUpdated(&'a [DeedUpdated<'a>]),
+    DeedRetracted(&'a [DeedRetracted<'a>]),
+    DeedCredited(&'a [DeedCredited<'a>]),
+    DeedUncredited(&'a [DeedUncredited<'a>]),
+    DeedVerified(&'a [DeedVerified<'a>]),
+    DeedUnverified(&'a [DeedUnverified<'a>]),
+    DeedReported(&'a [DeedReported<'a>]),
+    DeedRejected(&'a [DeedRejected<'a>]),
+    ReportsDismissed(&'a [ReportsDismissed<'a>]),
+    DonationReceived(&'a [DonationReceived<'a>]),
+    DonationPaid(&'a [DonationPaid<'a>]),
+    DirectDonationReceived(&'a [DirectDonationReceived<'a>]),
+    DonationScheduleCreated(&'a [DonationScheduleCreated<'a>]),
+    DonationScheduleCancelled(&'a [DonationScheduleCancelled<'a>]),
+    TokenDonationReceived(&'a [TokenDonationReceived<'a>]),
+    TokenDonationPaid(&'a [TokenDonationPaid<'a>]),
+    BountyCreated(&'a [BountyCreated<'a>]),
+    BountySubmitted(&'a [BountySubmitted<'a>]),
+    BountyPaid(&'a [BountyPaid<'a>]),
+    BountyRefunded(&'a [BountyRefunded<'a>]),
+    FundingRoundOpened(&'a [FundingRoundOpened<'a>]),
+    RoundContribution(&'a [RoundContribution<'a>]),
+    FundingRoundClosed(&'a [FundingRoundClosed<'a>]),
+    RoundFundsClaimed(&'a [RoundFundsClaimed<'a>]),
+    RoundFundsSwept(&'a [RoundFundsSwept<'a>]),
+    RoundContributionRefunded(&'a [RoundContributionRefunded<'a>]),
+    RoleGranted(&'a [RoleGranted<'a>]),
+    RoleRevoked(&'a [RoleRevoked<'a>]),
+    OwnershipTransferStarted(&'a [OwnershipTransferStarted<'a>]),
+    OwnershipTransferred(&'a [OwnershipTransferred<'a>]),
+    FeaturePaused(&'a [FeaturePaused<'a>]),
+    FeatureUnpaused(&'a [FeatureUnpaused<'a>]),
+}
+
+impl SocialEventKind<'_> {
+    fn version(&self) -> &'static str {
+        match self {
+            SocialEventKind::DeedAdded(_) => "1.0.0",
+            SocialEventKind::DeedUpdated(_) => "1.0.0",
+            SocialEventKind::DeedRetracted(_) => "1.0.0",
+            SocialEventKind::DeedCredited(_) => "1.0.0",
+            SocialEventKind::DeedUncredited(_) => "1.0.0",
+            SocialEventKind::DeedVerified(_) => "1.0.0",
+            SocialEventKind::DeedUnverified(_) => "1.0.0",
+            SocialEventKind::DeedReported(_) => "1.0.0",
+            SocialEventKind::DeedRejected(_) => "1.0.0",
+            SocialEventKind::ReportsDismissed(_) => "1.0.0",
+            SocialEventKind::DonationReceived(_) => "1.0.0",
+            SocialEventKind::DonationPaid(_) => "1.0.0",
+            SocialEventKind::DirectDonationReceived(_) => "1.0.0",
+            SocialEventKind::DonationScheduleCreated(_) => "1.0.0",
+            SocialEventKind::DonationScheduleCancelled(_) => "1.0.0",
+            SocialEventKind::TokenDonationReceived(_) => "1.0.0",
+            SocialEventKind::TokenDonationPaid(_) => "1.0.0",
+            SocialEventKind::BountyCreated(_) => "1.0.0",
+            SocialEventKind::BountySubmitted(_) => "1.0.0",
+            SocialEventKind::BountyPaid(_) => "1.0.0",
+            SocialEventKind::BountyRefunded(_) => "1.0.0",
+            SocialEventKind::FundingRoundOpened(_) => "1.0.0",
+            SocialEventKind::RoundContribution(_) => "1.0.0",
+            SocialEventKind::FundingRoundClosed(_) => "1.0.0",
+            SocialEventKind::RoundFundsClaimed(_) => "1.0.0",
+            SocialEventKind::RoundFundsSwept(_) => "1.0.0",
+            SocialEventKind::RoundContributionRefunded(_) => "1.0.0",
+            SocialEventKind::RoleGranted(_) => "1.0.0",
+            SocialEventKind::RoleRevoked(_) => "1.0.0",
+            SocialEventKind::OwnershipTransferStarted(_) => "1.0.0",
+            SocialEventKind::OwnershipTransferred(_) => "1.0.0",
+            SocialEventKind::FeaturePaused(_) => "1.0.0",
+            SocialEventKind::FeatureUnpaused(_) => "1.0.0",
+        }
+    }
+
+    fn emit(self) {
+        let event = SocialEvent { standard: STANDARD, version: self.version(), event_kind: self };
+        // Events cannot fail to serialize so fine to panic on error
+        let json = near_sdk::serde_json::to_string(&event).ok().unwrap_or_else(|| env::abort());
+        env::log_str(&format!("EVENT_JSON:{}", json));
+    }
+}
+
+#[derive(Serialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+struct SocialEvent<'a> {
+    standard: &'static str,
+    version: &'static str,
+    #[serde(flatten)]
+    event_kind: SocialEventKind<'a>,
+}
+
+/// Implements `emit` and `emit_many` for an event data struct.
+macro_rules! impl_emit {
+    ($data:ident) => {
+        impl $data<'_> {
+            /// Logs the event to the host.
+            pub fn emit(self) {
+                Self::emit_many(&[self])
+            }
+
+            /// Logs a single event with the data of all given events.
+            pub fn emit_many(data: &[$data<'_>]) {
+                SocialEventKind::$data(data).emit()
+            }
+        }
+    };
+}
+
+/// A deed was posted.
+#[must_use]
+#[derive(Serialize, Debug, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct DeedAdded<'a> {
+    pub deed_id: u64,
+    pub author: &'a AccountId,
+}
+impl_emit!(DeedAdded);
+
+/// The texts of a deed were changed by its author.
+#[must_use]
+#[derive(Serialize, Debug, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct DeedUpdated<'a> {
+    pub deed_id: u64,
+    pub author: &'a AccountId,
+}
+impl_emit!(DeedUpdated);
+
+/// A deed was retracted by its author and the `burned` DEED it earned were burned.
+#[must_use]
+#[derive(Serialize, Debug, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct DeedRetracted<'a> {
+    pub deed_id: u64,
+    pub author: &'a AccountId,
+    pub burned: &'a U128,
+}
+impl_emit!(DeedRetracted);
+
+/// A deed was credited and `amount` DEED were minted to its author.
+#[must_use]
+#[derive(Serialize, Debug, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct DeedCredited<'a> {
+    pub deed_id: u64,
+    pub author: &'a AccountId,
+    pub creditor: &'a AccountId,
+    pub amount: &'a U128,
+}
+impl_emit!(DeedCredited);
+
+/// A creditor withdrew its credit and `amount` DEED were burned from the author.
+#[must_use]
+#[derive(Serialize, Debug, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct DeedUncredited<'a> {
+    pub deed_id: u64,
+    pub author: &'a AccountId,
+    pub creditor: &'a AccountId,
+    pub amount: &'a U128,
+}
+impl_emit!(DeedUncredited);
+
+/// A deed reached the verification threshold.
+#[must_use]
+#[derive(Serialize, Debug, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct DeedVerified<'a> {
+    pub deed_id: u64,
+    pub author: &'a AccountId,
+}
+impl_emit!(DeedVerified);
+
+/// Withdrawn credits brought a verified deed below the verification threshold.
+#[must_use]
+#[derive(Serialize, Debug, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct DeedUnverified<'a> {
+    pub deed_id: u64,
+    pub author: &'a AccountId,
+}
+impl_emit!(DeedUnverified);
+
+/// A deed was reported as fraudulent.
+#[must_use]
+#[derive(Serialize, Debug, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct DeedReported<'a> {
+    pub deed_id: u64,
+    pub author: &'a AccountId,
+    pub reporter: &'a AccountId,
+}
+impl_emit!(DeedReported);
+
+/// A moderator rejected a deed and the `slashed` DEED it earned were burned from the author.
+#[must_use]
+#[derive(Serialize, Debug, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct DeedRejected<'a> {
+    pub deed_id: u64,
+    pub author: &'a AccountId,
+    pub moderator: &'a AccountId,
+    pub slashed: &'a U128,
+}
+impl_emit!(DeedRejected);
+
+/// A moderator dismissed the reports of a deed.
+#[must_use]
+#[derive(Serialize, Debug, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ReportsDismissed<'a> {
+    pub deed_id: u64,
+    pub moderator: &'a AccountId,
+}
+impl_emit!(ReportsDismissed);
+
+/// A donation of `amount` yoctoNEAR was distributed to the DEED holders.
+#[must_use]
+#[derive(Serialize, Debug, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct DonationReceived<'a> {
+    pub donor: &'a AccountId,
+    pub amount: &'a U128,
+}
+impl_emit!(DonationReceived);
+
+/// Accrued donations of `amount` yoctoNEAR were paid out to `account_id`.
+#[must_use]
+#[derive(Serialize, Debug, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct DonationPaid<'a> {
+    pub account_id: &'a AccountId,
+    pub amount: &'a U128,
+}
+impl_emit!(DonationPaid);
+
+/// A donation of `amount` yoctoNEAR was made to `account_id` alone, for the deed `deed_id` if set.
+#[must_use]
+#[derive(Serialize, Debug, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct DirectDonationReceived<'a> {
+    pub donor: &'a AccountId,
+    pub account_id: &'a AccountId,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deed_id: Option<u64>,
+    pub amount: &'a U128,
+}
+impl_emit!(DirectDonationReceived);
+
+/// `donor` scheduled `periods` donations of `amount_per_period` yoctoNEAR.
+#[must_use]
+#[derive(Serialize, Debug, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct DonationScheduleCreated<'a> {
+    pub schedule_id: u64,
+    pub donor: &'a AccountId,
+    pub amount_per_period: &'a U128,
+    pub periods: u64,
+}
+impl_emit!(DonationScheduleCreated);
+
+/// A donation schedule was cancelled and its unspent budget refunded to `donor`.
+#[must_use]
+#[derive(Serialize, Debug, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct DonationScheduleCancelled<'a> {
+    pub schedule_id: u64,
+    pub donor: &'a AccountId,
+    pub refund: &'a U128,
+}
+impl_emit!(DonationScheduleCancelled);
+
+/// A donation of `amount` of the fungible token `token_id` was distributed to the DEED holders.
+#[must_use]
+#[derive(Serialize, Debug, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct TokenDonationReceived<'a> {
+    pub token_id: &'a AccountId,
+    pub donor: &'a AccountId,
+    pub amount: &'a U128,
+}
+impl_emit!(TokenDonationReceived);
+
+/// Accrued donations of `amount` of the fungible token `token_id` were paid out to `account_id`.
+#[must_use]
+#[derive(Serialize, Debug, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct TokenDonationPaid<'a> {
+    pub token_id: &'a AccountId,
+    pub account_id: &'a AccountId,
+    pub amount: &'a U128,
+}
+impl_emit!(TokenDonationPaid);
+
+/// A bounty with an escrowed `reward` was posted.
+#[must_use]
+#[derive(Serialize, Debug, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct BountyCreated<'a> {
+    pub bounty_id: u64,
+    pub sponsor: &'a AccountId,
+    pub reward: &'a U128,
+}
+impl_emit!(BountyCreated);
+
+/// A deed was submitted to a bounty.
+#[must_use]
+#[derive(Serialize, Debug, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct BountySubmitted<'a> {
+    pub bounty_id: u64,
+    pub deed_id: u64,
+    pub author: &'a AccountId,
+}
+impl_emit!(BountySubmitted);
+
+/// A submission of a bounty was accepted and `amount` yoctoNEAR were paid to its author.
+#[must_use]
+#[derive(Serialize, Debug, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct BountyPaid<'a> {
+    pub bounty_id: u64,
+    pub deed_id: u64,
+    pub author: &'a AccountId,
+    pub amount: &'a U128,
+}
+impl_emit!(BountyPaid);
+
+/// The unpaid escrow of a bounty was refunded to its sponsor.
+#[must_use]
+#[derive(Serialize, Debug, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct BountyRefunded<'a> {
+    pub bounty_id: u64,
+    pub sponsor: &'a AccountId,
+    pub amount: &'a U128,
+}
+impl_emit!(BountyRefunded);
+
+/// A funding round with a `matching_pool` was opened.
+#[must_use]
+#[derive(Serialize, Debug, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct FundingRoundOpened<'a> {
+    pub round_id: u64,
+    pub sponsor: &'a AccountId,
+    pub matching_pool: &'a U128,
+}
+impl_emit!(FundingRoundOpened);
+
+/// `donor` contributed `amount` yoctoNEAR to a deed in a funding round.
+#[must_use]
+#[derive(Serialize, Debug, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct RoundContribution<'a> {
+    pub round_id: u64,
+    pub deed_id: u64,
+    pub donor: &'a AccountId,
+    pub amount: &'a U128,
+}
+impl_emit!(RoundContribution);
+
+/// A funding round ended after receiving `contributions` yoctoNEAR in total.
+#[must_use]
+#[derive(Serialize, Debug, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct FundingRoundClosed<'a> {
+    pub round_id: u64,
+    pub contributions: &'a U128,
+}
+impl_emit!(FundingRoundClosed);
+
+/// The contributions to a deed and its share of the matching pool were paid to its author.
+#[must_use]
+#[derive(Serialize, Debug, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct RoundFundsClaimed<'a> {
+    pub round_id: u64,
+    pub deed_id: u64,
+    pub author: &'a AccountId,
+    pub contributions: &'a U128,
+    pub matched: &'a U128,
+}
+impl_emit!(RoundFundsClaimed);
+
+/// The `amount` of the matching pool of a round that was not paid to authors, including its
+/// rounding dust, was returned to its sponsor.
+#[must_use]
+#[derive(Serialize, Debug, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct RoundFundsSwept<'a> {
+    pub round_id: u64,
+    pub sponsor: &'a AccountId,
+    pub amount: &'a U128,
+}
+impl_emit!(RoundFundsSwept);
+
+/// The contribution of `donor` to a deed that was retracted or rejected was refunded.
+#[must_use]
+#[derive(Serialize, Debug, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct RoundContributionRefunded<'a> {
+    pub round_id: u64,
+    pub deed_id: u64,
+    pub donor: &'a AccountId,
+    pub amount: &'a U128,
+}
+impl_emit!(RoundContributionRefunded);
+
+/// `role` was granted to `account_id` by `by`.
+#[must_use]
+#[derive(Serialize, Debug, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct RoleGranted<'a> {
+    pub role: Role,
+    pub account_id: &'a AccountId,
+    pub by: &'a AccountId,
+}
+impl_emit!(RoleGranted);
+
+/// `role` was revoked from `account_id` by `by`.
+#[must_use]
+#[derive(Serialize, Debug, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct RoleRevoked<'a> {
+    pub role: Role,
+    pub account_id: &'a AccountId,
+    pub by: &'a AccountId,
+}
+impl_emit!(RoleRevoked);
+
+/// The owner proposed `pending_owner` as the next owner.
+#[must_use]
+#[derive(Serialize, Debug, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct OwnershipTransferStarted<'a> {
+    pub owner: &'a AccountId,
+    pub pending_owner: &'a AccountId,
+}
+impl_emit!(OwnershipTransferStarted);
+
+/// The pending owner accepted the ownership.
+#[must_use]
+#[derive(Serialize, Debug, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct OwnershipTransferred<'a> {
+    pub old_owner: &'a AccountId,
+    pub new_owner: &'a AccountId,
+}
+impl_emit!(OwnershipTransferred);
+
+/// `feature` was paused by `by`.
+#[must_use]
+#[derive(Serialize, Debug, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct FeaturePaused<'a> {
+    pub feature: Feature,
+    pub by: &'a AccountId,
+}
+impl_emit!(FeaturePaused);
+
+/// `feature` was unpaused by `by`.
+#[must_use]
+#[derive(Serialize, Debug, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct FeatureUnpaused<'a> {
+    pub feature: Feature,
+    pub by: &'a AccountId,
+}
+impl_emit!(FeatureUnpaused);
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_sdk::test_utils::{accounts, get_logs};
+    use near_sdk::testing_env;
+
+    use crate::tests::get_context;
+    use super::*;
+
+    #[test]
+    fn test_event_format() {
+        testing_env!(get_context(accounts(1)).build());
+        DeedCredited { deed_id: 3, author: &accounts(1), creditor: &accounts(2), amount: &1.into() }.emit();
+
+        assert_eq!(
+            get_logs(),
+            [r#"EVENT_JSON:{"standard":"social_bounty","version":"1.0.0","event":"deed_credited","data":[{"deed_id":3,"author":"bob","creditor":"charlie","amount":"1"}]}"#]
+        );
+    }
+}
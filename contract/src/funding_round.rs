@@ -0,0 +1,614 @@
+//! Quadratic funding rounds.
+//!
+//! A treasurer opens a round with a NEAR matching pool. While the round runs, donors contribute to
+//! individual deeds. Once it ended, the matching pool is split between the deeds proportional to
+//! the square of the sum of the square roots of their contributions, so the number of donors
+//! counts more than the size of the contributions. The authors claim the match together with the
+//! contributions to their deeds.
+//!
+//! Every contribution updates the sum of the square roots of its deed and the sum of the squares
+//! of all deeds of the round, so closing a round does not depend on the number of deeds. The
+//! square roots are integer square roots of yoctoNEAR and the matches are rounded down, so the
+//! contract never pays out more than the matching pool.
+//!
+//! Only active and verified deeds can be claimed. The donors of deeds that were retracted or
+//! rejected get their contributions refunded instead. Once every deed was claimed, or at the latest
+//! `CLAIM_PERIOD` after the end of the round, a treasurer sweeps the part of the matching pool that
+//! was not paid to authors back to the sponsor: the rounding dust and the matches of deeds that
+//! were not claimed. Contributions are never swept, authors still claim them afterwards.
+
+use crate::*;
+use crate::access_control::Role;
+use crate::pause::Feature;
+use near_sdk::json_types::U64;
+
+/// Time in nanoseconds after the end of a round during which its funds are kept for the authors.
+pub const CLAIM_PERIOD: u64 = 30 * 24 * 60 * 60 * 1_000_000_000;
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct FundingRound {
+    id: u64,
+    sponsor: AccountId,
+    title: String,
+    /// Block timestamp in nanoseconds from which contributions are accepted.
+    starts_at: u64,
+    /// Block timestamp in nanoseconds from which no contributions are accepted anymore.
+    ends_at: u64,
+    /// The attached deposit of the sponsor without the storage costs.
+    matching_pool: Balance,
+    contributions: Balance,
+    /// The sum of the weights of all deeds of the round.
+    total_weight: u128,
+    closed: bool,
+    deeds: UnorderedMap<u64, RoundDeed>,
+    /// The contributions of every donor to every deed of the round.
+    donors: LookupMap<(u64, AccountId), Balance>,
+    /// yoctoNEAR of the matching pool paid to authors or returned to the sponsor.
+    paid: Balance,
+    /// Number of deeds whose funds were claimed.
+    claims: u64,
+    /// Whether the rest of the round was returned to the sponsor.
+    swept: bool
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Default)]
+pub struct RoundDeed {
+    contributions: Balance,
+    /// The sum of the square roots of the contributions of every donor.
+    sqrt_sum: u128,
+    claimed: bool
+}
+
+impl RoundDeed {
+    fn weight(&self) -> u128 {
+        self.sqrt_sum * self.sqrt_sum
+    }
+
+    /// Adds `amount` to the `previous` contributions of a donor.
+    fn add_contribution(&mut self, previous: Balance, amount: Balance) {
+        self.sqrt_sum = self.sqrt_sum - isqrt(previous) + isqrt(previous + amount);
+        self.contributions += amount;
+    }
+}
+
+impl FundingRound {
+    pub fn new(
+        id: u64,
+        sponsor: AccountId,
+        title: String,
+        starts_at: u64,
+        ends_at: u64
+    ) -> Self{
+        Self {
+            id, sponsor, title, starts_at, ends_at,
+            matching_pool: 0,
+            contributions: 0,
+            total_weight: 0,
+            closed: false,
+            deeds: UnorderedMap::new(StorageKey::RoundDeeds { round_id: id }),
+            donors: LookupMap::new(StorageKey::RoundDonors { round_id: id }),
+            paid: 0,
+            claims: 0,
+            swept: false
+        }
+    }
+
+    pub fn is_open(&self) -> bool {
+        let now = env::block_timestamp();
+        !self.closed && self.starts_at <= now && now < self.ends_at
+    }
+
+    /// The part of the matching pool `deed` receives.
+    fn matched(&self, deed: &RoundDeed) -> Balance {
+        if self.total_weight == 0 {
+            return 0;
+        }
+        mul_div(self.matching_pool, deed.weight(), self.total_weight)
+    }
+}
+
+/// Integer square root, rounded down.
+pub fn isqrt(n: u128) -> u128 {
+    if n < 2 {
+        return n;
+    }
+    let mut x = n;
+    let mut y = (n >> 1) + (n & 1);
+    while y < x {
+        x = y;
+        y = (x + n / x) / 2;
+    }
+    x
+}
+
+/// Computes `a * b / c` rounded down without overflowing in the product. Requires `b <= c`, which
+/// makes sure the result fits into `a`.
+pub fn mul_div(a: u128, b: u128, c: u128) -> u128 {
+    assert!(b <= c && c < 1 << 127, "Invalid arguments of mul_div.");
+    // The accumulated product is always `quotient * c + remainder`.
+    let (mut quotient, mut remainder) = (0u128, 0u128);
+    let (a_quotient, a_remainder) = (a / c, a % c);
+    for bit in (0..128 - b.leading_zeros()).rev() {
+        quotient *= 2;
+        remainder *= 2;
+        if remainder >= c {
+            quotient += 1;
+            remainder -= c;
+        }
+        if b >> bit & 1 == 1 {
+            quotient += a_quotient;
+            remainder += a_remainder;
+            if remainder >= c {
+                quotient += 1;
+                remainder -= c;
+            }
+        }
+    }
+    quotient
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct SerializableFundingRound {
+    id: u64,
+    sponsor: AccountId,
+    title: String,
+    starts_at: U64,
+    ends_at: U64,
+    matching_pool: U128,
+    contributions: U128,
+    closed: bool,
+    deeds: u64
+}
+
+impl From<&FundingRound> for SerializableFundingRound {
+    fn from(round: &FundingRound) -> Self {
+        Self {
+            id: round.id,
+            sponsor: round.sponsor.clone(),
+            title: round.title.clone(),
+            starts_at: round.starts_at.into(),
+            ends_at: round.ends_at.into(),
+            matching_pool: round.matching_pool.into(),
+            contributions: round.contributions.into(),
+            closed: round.closed,
+            deeds: round.deeds.len()
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct SerializableRoundDeed {
+    deed_id: u64,
+    contributions: U128,
+    /// The part of the matching pool the deed receives if the round ended now.
+    matched: U128,
+    claimed: bool
+}
+
+impl Contract {
+    fn internal_get_round(&self, round_id: u64) -> FundingRound {
+        assert!(self.funding_rounds.len() > round_id, "The round id is out of range.");
+        self.funding_rounds.get(round_id).unwrap()
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Opens a funding round that accepts contributions from `starts_at` (now if not set) until
+    /// `ends_at`. The attached deposit minus the storage costs is the matching pool. Can only be
+    /// called by treasurers.
+    #[payable]
+    pub fn open_funding_round(
+        &mut self,
+        title: String,
+        starts_at: Option<U64>,
+        ends_at: U64
+    ) -> u64
+    {
+        let initial_storage_usage = env::storage_usage();
+
+        self.assert_role(Role::Treasurer);
+        self.assert_not_paused(Feature::Donations);
+        let starts_at = starts_at.map_or_else(env::block_timestamp, u64::from);
+        require!(ends_at.0 > starts_at.max(env::block_timestamp()), "The round must end in the future and after its start.");
+        let id = self.funding_rounds.len();
+        let mut round = FundingRound::new(id, env::predecessor_account_id(), title, starts_at, ends_at.0);
+        self.funding_rounds.push(&round);
+
+        round.matching_pool = calculate_and_check_deposit(env::storage_usage() - initial_storage_usage);
+        require!(round.matching_pool > 0, "The attached deposit does not cover a matching pool.");
+        self.funding_rounds.replace(id, &round);
+        events::FundingRoundOpened { round_id: id, sponsor: &round.sponsor, matching_pool: &round.matching_pool.into() }.emit();
+        id
+    }
+
+    /// Contributes the attached deposit minus the storage costs to the deed `deed_id` in the round
+    /// `round_id`. The contribution is paid to the author when the round has ended.
+    #[payable]
+    pub fn contribute_to_round(
+        &mut self,
+        round_id: u64,
+        deed_id: u64
+    )
+    {
+        let initial_storage_usage = env::storage_usage();
+
+        self.assert_not_paused(Feature::Donations);
+        let mut round = self.internal_get_round(round_id);
+        require!(round.is_open(), "The round does not accept contributions.");
+        let deed = self.internal_get_deed(deed_id);
+        deed.assert_active();
+        let donor = env::predecessor_account_id();
+        require!(donor != deed.author, "Authors cannot contribute to their own deeds.");
+
+        // Store the entries first, a contribution does not change their size.
+        let key = (deed_id, donor.clone());
+        let previous = round.donors.get(&key).unwrap_or_default();
+        let mut round_deed = round.deeds.get(&deed_id).unwrap_or_default();
+        round.donors.insert(&key, &previous);
+        round.deeds.insert(&deed_id, &round_deed);
+        let amount = calculate_and_check_deposit(env::storage_usage() - initial_storage_usage);
+        require!(amount > 0, "The deposit does not cover more than the storage costs.");
+
+        let previous_weight = round_deed.weight();
+        round_deed.add_contribution(previous, amount);
+        round.total_weight = round.total_weight - previous_weight + round_deed.weight();
+        round.contributions += amount;
+        round.donors.insert(&key, &(previous + amount));
+        round.deeds.insert(&deed_id, &round_deed);
+        self.funding_rounds.replace(round_id, &round);
+        events::RoundContribution { round_id, deed_id, donor: &donor, amount: &amount.into() }.emit();
+    }
+
+    /// Closes the round `round_id` once it has ended. If nobody contributed, the matching pool is
+    /// refunded to the sponsor.
+    pub fn close_funding_round(&mut self, round_id: u64) {
+        let mut round = self.internal_get_round(round_id);
+        require!(env::block_timestamp() >= round.ends_at, "The round has not ended yet.");
+        require!(!round.closed, "The round was already closed.");
+        round.closed = true;
+        if round.total_weight == 0 {
+            round.paid = round.matching_pool;
+            round.swept = true;
+        }
+        self.funding_rounds.replace(round_id, &round);
+        events::FundingRoundClosed { round_id, contributions: &round.contributions.into() }.emit();
+
+        if round.total_weight == 0 {
+            Promise::new(round.sponsor).transfer(round.matching_pool);
+        }
+    }
+
+    /// Pays the contributions to the active deed `deed_id` in the closed round `round_id` together
+    /// with its match to the author. Once the round was swept, only the contributions are paid.
+    /// Returns the paid amount.
+    pub fn claim_round_funds(&mut self, round_id: u64, deed_id: u64) -> U128 {
+        self.assert_not_paused(Feature::Donations);
+        let mut round = self.internal_get_round(round_id);
+        require!(round.closed, "The round was not closed yet.");
+        let deed = self.internal_get_deed(deed_id);
+        deed.assert_active();
+        let author = deed.author;
+        assert_eq!(author, env::predecessor_account_id(), "Only the author can claim the funds of a deed.");
+        let mut round_deed = round.deeds.get(&deed_id).expect("The deed did not receive any contributions.");
+        require!(!round_deed.claimed, "The funds were already claimed.");
+        round_deed.claimed = true;
+        round.deeds.insert(&deed_id, &round_deed);
+
+        let matched = if round.swept { 0 } else { round.matched(&round_deed) };
+        let amount = round_deed.contributions + matched;
+        round.paid += matched;
+        round.claims += 1;
+        self.funding_rounds.replace(round_id, &round);
+        events::RoundFundsClaimed {
+            round_id,
+            deed_id,
+            author: &author,
+            contributions: &round_deed.contributions.into(),
+            matched: &matched.into()
+        }.emit();
+        Promise::new(author).transfer(amount);
+        amount.into()
+    }
+
+    /// Refunds the contribution of the caller to the deed `deed_id` in the closed round `round_id`.
+    /// Only contributions to deeds that were retracted or rejected are refunded, their authors
+    /// cannot claim them. Returns the refunded amount.
+    pub fn refund_round_contribution(&mut self, round_id: u64, deed_id: u64) -> U128 {
+        self.assert_not_paused(Feature::Donations);
+        let mut round = self.internal_get_round(round_id);
+        require!(round.closed, "The round was not closed yet.");
+        require!(!self.internal_get_deed(deed_id).is_active(), "The contributions to an active deed are paid to its author.");
+        let donor = env::predecessor_account_id();
+        let amount = round.donors.get(&(deed_id, donor.clone())).unwrap_or_default();
+        require!(amount > 0, "There is no contribution to refund.");
+        let mut round_deed = round.deeds.get(&deed_id).unwrap();
+        round_deed.contributions -= amount;
+        round.deeds.insert(&deed_id, &round_deed);
+        round.donors.remove(&(deed_id, donor.clone()));
+        events::RoundContributionRefunded { round_id, deed_id, donor: &donor, amount: &amount.into() }.emit();
+        Promise::new(donor).transfer(amount);
+        amount.into()
+    }
+
+    /// Returns the part of the matching pool of the closed round `round_id` that was not paid to
+    /// authors to its sponsor, once every deed was claimed or `CLAIM_PERIOD` passed since the end
+    /// of the round. Afterwards authors only claim the contributions to their deeds. Can only be
+    /// called by treasurers. Returns the swept amount.
+    pub fn sweep_funding_round(&mut self, round_id: u64) -> U128 {
+        self.assert_role(Role::Treasurer);
+        self.assert_not_paused(Feature::Donations);
+        let mut round = self.internal_get_round(round_id);
+        require!(round.closed, "The round was not closed yet.");
+        require!(!round.swept, "The funds of the round were already swept.");
+        require!(
+            round.claims == round.deeds.len() || env::block_timestamp() >= round.ends_at + CLAIM_PERIOD,
+            "The funds of the round can still be claimed."
+        );
+        let amount = round.matching_pool - round.paid;
+        round.paid += amount;
+        round.swept = true;
+        self.funding_rounds.replace(round_id, &round);
+        events::RoundFundsSwept { round_id, sponsor: &round.sponsor, amount: &amount.into() }.emit();
+        if amount > 0 {
+            Promise::new(round.sponsor).transfer(amount);
+        }
+        amount.into()
+    }
+
+    pub fn get_funding_rounds_count(&self) -> u64 {
+        self.funding_rounds.len()
+    }
+
+    pub fn get_funding_round(&self, round_id: u64) -> SerializableFundingRound {
+        (&self.internal_get_round(round_id)).into()
+    }
+
+    /// Returns the deeds that received contributions in the round `round_id`.
+    pub fn funding_round_deeds(&self, round_id: u64, from_index: Option<u64>, limit: Option<u64>) -> Vec<SerializableRoundDeed> {
+        let round = self.internal_get_round(round_id);
+        let start_index = from_index.unwrap_or_default();
+        let limit = limit.unwrap_or(u64::MAX);
+        require!(limit != 0, "Cannot provide limit of 0.");
+        let keys = round.deeds.keys_as_vector();
+        let values = round.deeds.values_as_vector();
+        (start_index..keys.len())
+            .take(limit as usize)
+            .map(|index| {
+                let round_deed = values.get(index).unwrap();
+                SerializableRoundDeed {
+                    deed_id: keys.get(index).unwrap(),
+                    contributions: round_deed.contributions.into(),
+                    matched: round.matched(&round_deed).into(),
+                    claimed: round_deed.claimed
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::testing_env;
+
+    use crate::tests::{call, get_context, ONE_NEAR, SAFE_STORAGE_COST, TOTAL_SUPPLY};
+    use super::*;
+
+    const ENDS_AT: u64 = 1_000_000;
+
+    /// Sets up a contract with deeds of accounts(1) and accounts(3) and a round with 10 NEAR.
+    fn setup(context: &mut VMContextBuilder) -> Contract {
+        testing_env!(context.block_timestamp(0).build());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into());
+        for account_id in [accounts(1), accounts(3)].iter() {
+            call(context.block_timestamp(0), account_id.clone(), SAFE_STORAGE_COST);
+            contract.storage_deposit(None, None);
+            contract.add_deed(account_id.clone(), "title".into(), "description".into(), "proof".into(), None, None);
+        }
+        call(context.block_timestamp(0), accounts(2), 10 * ONE_NEAR);
+        contract.open_funding_round("round".into(), None, ENDS_AT.into());
+        contract
+    }
+
+    #[test]
+    fn test_integer_math() {
+        let roots: Vec<u128> = [0, 1, 2, 3, 4, 15, 16, 17, 10u128.pow(24)].iter().map(|n| isqrt(*n)).collect();
+        assert_eq!(roots, vec![0, 1, 1, 1, 2, 3, 4, 4, 10u128.pow(12)]);
+        assert_eq!(isqrt(u128::MAX), u64::MAX as u128);
+
+        assert_eq!(mul_div(10, 3, 4), 7);
+        assert_eq!(mul_div(u128::MAX, 3, 3), u128::MAX);
+        assert_eq!(mul_div(10u128.pow(30), 10u128.pow(30) - 1, 10u128.pow(30)), 10u128.pow(30) - 1);
+    }
+
+    #[test]
+    fn test_many_donors_receive_more_matching() {
+        let mut context = get_context(accounts(2));
+        let mut contract = setup(&mut context);
+        let matching_pool = contract.get_funding_round(0).matching_pool.0;
+
+        call(context.block_timestamp(1), accounts(4), 4 * ONE_NEAR);
+        contract.contribute_to_round(0, 0);
+        for donor in [accounts(0), accounts(2), accounts(4), accounts(5)].iter() {
+            call(context.block_timestamp(1), donor.clone(), ONE_NEAR);
+            contract.contribute_to_round(0, 1);
+        }
+
+        let deeds = contract.funding_round_deeds(0, None, None);
+        assert!(deeds[1].matched.0 > 3 * deeds[0].matched.0);
+        assert!(deeds[0].matched.0 + deeds[1].matched.0 <= matching_pool);
+
+        call(context.block_timestamp(ENDS_AT), accounts(0), 0);
+        contract.close_funding_round(0);
+        call(context.block_timestamp(ENDS_AT), accounts(3), 0);
+        let paid = contract.claim_round_funds(0, 1).0;
+        assert_eq!(paid, deeds[1].contributions.0 + deeds[1].matched.0);
+        assert!(contract.funding_round_deeds(0, Some(1), None)[0].claimed);
+    }
+
+    #[test]
+    #[should_panic(expected = "The round does not accept contributions.")]
+    fn test_cannot_contribute_after_end() {
+        let mut context = get_context(accounts(2));
+        let mut contract = setup(&mut context);
+
+        call(context.block_timestamp(ENDS_AT), accounts(4), ONE_NEAR);
+        contract.contribute_to_round(0, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "The round was not closed yet.")]
+    fn test_cannot_claim_before_close() {
+        let mut context = get_context(accounts(2));
+        let mut contract = setup(&mut context);
+
+        call(context.block_timestamp(1), accounts(4), ONE_NEAR);
+        contract.contribute_to_round(0, 0);
+        call(context.block_timestamp(ENDS_AT), accounts(1), 0);
+        contract.claim_round_funds(0, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "The deed is not active anymore.")]
+    fn test_cannot_claim_for_retracted_deed() {
+        let mut context = get_context(accounts(2));
+        let mut contract = setup(&mut context);
+
+        call(context.block_timestamp(1), accounts(4), ONE_NEAR);
+        contract.contribute_to_round(0, 0);
+        call(context.block_timestamp(2), accounts(1), 0);
+        contract.retract_deed(0);
+        call(context.block_timestamp(ENDS_AT), accounts(0), 0);
+        contract.close_funding_round(0);
+        call(context.block_timestamp(ENDS_AT), accounts(1), 0);
+        contract.claim_round_funds(0, 0);
+    }
+
+    #[test]
+    fn test_sweep_returns_dust_to_sponsor() {
+        let mut context = get_context(accounts(2));
+        let mut contract = setup(&mut context);
+        let matching_pool = contract.get_funding_round(0).matching_pool.0;
+
+        call(context.block_timestamp(1), accounts(4), ONE_NEAR);
+        contract.contribute_to_round(0, 0);
+        for donor in [accounts(0), accounts(5)].iter() {
+            call(context.block_timestamp(1), donor.clone(), ONE_NEAR / 3);
+            contract.contribute_to_round(0, 1);
+        }
+        call(context.block_timestamp(ENDS_AT), accounts(0), 0);
+        contract.close_funding_round(0);
+        let mut paid = 0;
+        for (deed_id, author) in [(0, accounts(1)), (1, accounts(3))].iter() {
+            call(context.block_timestamp(ENDS_AT), author.clone(), 0);
+            paid += contract.claim_round_funds(0, *deed_id).0;
+        }
+
+        call(context.block_timestamp(ENDS_AT), accounts(2), 0);
+        let contributions = contract.get_funding_round(0).contributions.0;
+        let swept = contract.sweep_funding_round(0).0;
+        assert!(swept < matching_pool);
+        assert_eq!(paid + swept, matching_pool + contributions);
+    }
+
+    #[test]
+    fn test_sweep_unclaimed_funds_after_claim_period() {
+        let mut context = get_context(accounts(2));
+        let mut contract = setup(&mut context);
+
+        call(context.block_timestamp(1), accounts(4), ONE_NEAR);
+        contract.contribute_to_round(0, 0);
+        call(context.block_timestamp(1), accounts(4), ONE_NEAR);
+        contract.contribute_to_round(0, 1);
+        call(context.block_timestamp(ENDS_AT), accounts(0), 0);
+        contract.close_funding_round(0);
+        call(context.block_timestamp(ENDS_AT), accounts(1), 0);
+        contract.claim_round_funds(0, 0);
+        let deeds = contract.funding_round_deeds(0, None, None);
+
+        call(context.block_timestamp(ENDS_AT + CLAIM_PERIOD), accounts(2), 0);
+        let matching_pool = contract.get_funding_round(0).matching_pool.0;
+        assert_eq!(contract.sweep_funding_round(0).0, matching_pool - deeds[0].matched.0);
+        // The contributions are not swept, but the match is.
+        call(context.block_timestamp(ENDS_AT + CLAIM_PERIOD), accounts(3), 0);
+        assert_eq!(contract.claim_round_funds(0, 1), deeds[1].contributions);
+    }
+
+    #[test]
+    fn test_refund_contributions_to_retracted_deed() {
+        let mut context = get_context(accounts(2));
+        let mut contract = setup(&mut context);
+
+        for donor in [accounts(4), accounts(5)].iter() {
+            call(context.block_timestamp(1), donor.clone(), ONE_NEAR);
+            contract.contribute_to_round(0, 0);
+        }
+        call(context.block_timestamp(1), accounts(4), ONE_NEAR);
+        contract.contribute_to_round(0, 1);
+        let deeds = contract.funding_round_deeds(0, None, None);
+        call(context.block_timestamp(2), accounts(1), 0);
+        contract.retract_deed(0);
+        call(context.block_timestamp(ENDS_AT), accounts(0), 0);
+        contract.close_funding_round(0);
+
+        call(context.block_timestamp(ENDS_AT), accounts(4), 0);
+        let refunded = contract.refund_round_contribution(0, 0).0;
+        call(context.block_timestamp(ENDS_AT), accounts(3), 0);
+        contract.claim_round_funds(0, 1);
+        call(context.block_timestamp(ENDS_AT + CLAIM_PERIOD), accounts(2), 0);
+        let matching_pool = contract.get_funding_round(0).matching_pool.0;
+        assert_eq!(contract.sweep_funding_round(0).0, matching_pool - deeds[1].matched.0);
+        // Contributions are refunded after the sweep as well.
+        call(context.block_timestamp(ENDS_AT + CLAIM_PERIOD), accounts(5), 0);
+        assert_eq!(refunded + contract.refund_round_contribution(0, 0).0, deeds[0].contributions.0);
+        assert_eq!(contract.funding_round_deeds(0, None, Some(1))[0].contributions.0, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "There is no contribution to refund.")]
+    fn test_cannot_refund_twice() {
+        let mut context = get_context(accounts(2));
+        let mut contract = setup(&mut context);
+
+        call(context.block_timestamp(1), accounts(4), ONE_NEAR);
+        contract.contribute_to_round(0, 0);
+        call(context.block_timestamp(2), accounts(1), 0);
+        contract.retract_deed(0);
+        call(context.block_timestamp(ENDS_AT), accounts(0), 0);
+        contract.close_funding_round(0);
+        call(context.block_timestamp(ENDS_AT), accounts(4), 0);
+        contract.refund_round_contribution(0, 0);
+        contract.refund_round_contribution(0, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "The contributions to an active deed are paid to its author.")]
+    fn test_cannot_refund_contributions_to_active_deed() {
+        let mut context = get_context(accounts(2));
+        let mut contract = setup(&mut context);
+
+        call(context.block_timestamp(1), accounts(4), ONE_NEAR);
+        contract.contribute_to_round(0, 0);
+        call(context.block_timestamp(ENDS_AT), accounts(0), 0);
+        contract.close_funding_round(0);
+        call(context.block_timestamp(ENDS_AT), accounts(4), 0);
+        contract.refund_round_contribution(0, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "The funds of the round can still be claimed.")]
+    fn test_cannot_sweep_during_claim_period() {
+        let mut context = get_context(accounts(2));
+        let mut contract = setup(&mut context);
+
+        call(context.block_timestamp(1), accounts(4), ONE_NEAR);
+        contract.contribute_to_round(0, 0);
+        call(context.block_timestamp(ENDS_AT), accounts(0), 0);
+        contract.close_funding_round(0);
+        call(context.block_timestamp(ENDS_AT + CLAIM_PERIOD - 1), accounts(2), 0);
+        contract.sweep_funding_round(0);
+    }
+}
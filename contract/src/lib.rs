@@ -22,21 +22,194 @@ use near_contract_standards::fungible_token::metadata::{
 use near_contract_standards::fungible_token::FungibleToken;
 use near_contract_standards::fungible_token::core::FungibleTokenCore;
 use near_contract_standards::fungible_token::resolver::FungibleTokenResolver;
+use near_contract_standards::storage_management::{
+    StorageBalance, StorageBalanceBounds, StorageManagement,
+};
 use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
-use near_sdk::collections::{LazyOption, UnorderedSet, Vector};
-use near_sdk::json_types::U128;
+use near_sdk::collections::{LazyOption, LookupMap, UnorderedMap, UnorderedSet, Vector};
+use near_sdk::json_types::{U128, U64};
 use near_sdk::serde::{Serialize, Deserialize};
-use near_sdk::{env, log, near_bindgen, AccountId, Balance, PanicOnDefault, PromiseOrValue,
-               require, Promise};
+use near_sdk::{env, log, near_bindgen, AccountId, Balance, IntoStorageKey, PanicOnDefault,
+               PromiseOrValue, require, Promise};
+
+use crate::access_control::Role;
+use crate::bounty::Bounty;
+use crate::deed_index::DeedIndex;
+use crate::donation::DonationAccount;
+use crate::donation_history::Donation;
+use crate::donation_schedule::DonationSchedule;
+use crate::eligibility::CreditRules;
+use crate::funding_round::FundingRound;
+use crate::migration::{DeedBackfill, SocialDeedV1};
+use crate::moderation::Report;
+use crate::pause::Feature;
+use crate::rate_limit::{AccountUsage, RateLimits};
+use crate::reputation::CreditWeighting;
+use crate::token_donation::TokenPool;
+use crate::verification::VerificationThreshold;
+
+mod access_control;
+mod bounty;
+mod categories;
+mod deed_index;
+mod donation;
+mod donation_history;
+mod donation_schedule;
+mod eligibility;
+mod events;
+mod funding_round;
+mod migration;
+mod moderation;
+mod pause;
+mod rate_limit;
+mod reputation;
+mod timeline;
+mod token_donation;
+mod verification;
+
+/// Keys of all persistent collections.
+///
+/// The top level collections keep the single byte prefixes the contract has always used, so
+/// existing deployments keep their data. The deeds of the first version stay under `d`, new
+/// deployments store their deeds under `VersionedDeeds`. All other collections use the prefix `k`
+/// followed by a fixed tag of the variant and, for nested collections like the creditors of a
+/// deed, the Borsh encoding of its fields. The fixed width ids make these prefixes unambiguous, so
+/// the collections of deed 1 never share keys with those of deed 10.
+///
+/// The tags are part of the stored state and must never change. New variants take the next free
+/// tag and the tags of removed variants are not reused.
+pub(crate) enum StorageKey {
+    Token,
+    Metadata,
+    DonatableAccounts,
+    DonationAccounts,
+    Bounties,
+    VersionedDeeds,
+    Roles,
+    Reports,
+    RegisteredAt,
+    VerifiedAccounts,
+    RateLimitUsage,
+    Categories,
+    DeedsByCategory,
+    DeedsByTag,
+    AuthoredDeeds,
+    CreditedDeeds,
+    DonationTokens,
+    TokenDonationAccounts,
+    FundingRounds,
+    DonationSchedules,
+    ActiveDonationSchedules,
+    Donations,
+    DonorTotals,
+    DeedCreditors { deed_id: u64 },
+    BountySubmissions { bounty_id: u64 },
+    BountyWinners { bounty_id: u64 },
+    RoleMembers { role: Role },
+    CreditWeights { deed_id: u64 },
+    CreditTimestamps { deed_id: u64 },
+    CategoryDeeds { category: String },
+    TagDeeds { tag: String },
+    DeedsOfAuthor { account_id: AccountId },
+    DeedsOfCreditor { account_id: AccountId },
+    RoundDeeds { round_id: u64 },
+    RoundDonors { round_id: u64 },
+    RecordStorage,
+}
+
+impl IntoStorageKey for StorageKey {
+    fn into_storage_key(self) -> Vec<u8> {
+        let (tag, fields) = match self {
+            StorageKey::Token => return b"a".to_vec(),
+            StorageKey::Metadata => return b"m".to_vec(),
+            StorageKey::DonatableAccounts => return b"s".to_vec(),
+            StorageKey::DonationAccounts => return b"c".to_vec(),
+            StorageKey::Bounties => return b"b".to_vec(),
+            StorageKey::VersionedDeeds => (5, vec![]),
+            StorageKey::Roles => (6, vec![]),
+            StorageKey::Reports => (7, vec![]),
+            StorageKey::RegisteredAt => (8, vec![]),
+            StorageKey::VerifiedAccounts => (9, vec![]),
+            StorageKey::RateLimitUsage => (10, vec![]),
+            StorageKey::Categories => (11, vec![]),
+            StorageKey::DeedsByCategory => (12, vec![]),
+            StorageKey::DeedsByTag => (13, vec![]),
+            StorageKey::AuthoredDeeds => (14, vec![]),
+            StorageKey::CreditedDeeds => (15, vec![]),
+            StorageKey::DonationTokens => (16, vec![]),
+            StorageKey::TokenDonationAccounts => (17, vec![]),
+            StorageKey::FundingRounds => (18, vec![]),
+            StorageKey::DonationSchedules => (19, vec![]),
+            StorageKey::ActiveDonationSchedules => (20, vec![]),
+            StorageKey::Donations => (21, vec![]),
+            StorageKey::DonorTotals => (22, vec![]),
+            StorageKey::DeedCreditors { deed_id } => (23, deed_id.try_to_vec().unwrap()),
+            StorageKey::BountySubmissions { bounty_id } => (24, bounty_id.try_to_vec().unwrap()),
+            StorageKey::BountyWinners { bounty_id } => (25, bounty_id.try_to_vec().unwrap()),
+            StorageKey::RoleMembers { role } => (26, role.try_to_vec().unwrap()),
+            StorageKey::CreditWeights { deed_id } => (27, deed_id.try_to_vec().unwrap()),
+            StorageKey::CreditTimestamps { deed_id } => (28, deed_id.try_to_vec().unwrap()),
+            StorageKey::CategoryDeeds { category } => (29, category.try_to_vec().unwrap()),
+            StorageKey::TagDeeds { tag } => (30, tag.try_to_vec().unwrap()),
+            StorageKey::DeedsOfAuthor { account_id } => (31, account_id.try_to_vec().unwrap()),
+            StorageKey::DeedsOfCreditor { account_id } => (32, account_id.try_to_vec().unwrap()),
+            StorageKey::RoundDeeds { round_id } => (33, round_id.try_to_vec().unwrap()),
+            StorageKey::RoundDonors { round_id } => (34, round_id.try_to_vec().unwrap()),
+            StorageKey::RecordStorage => (35, vec![]),
+        };
+        [b"k".as_ref(), &[tag], &fields].concat()
+    }
+}
 
 #[near_bindgen]
 #[derive(BorshDeserialize, BorshSerialize, PanicOnDefault)]
 pub struct Contract {
     token: FungibleToken,
     metadata: LazyOption<FungibleTokenMetadata>,
-    deeds: Vector<SocialDeed>,
+    deeds: Vector<VersionedDeed>,
     owner: AccountId,
-    donatable_accounts: UnorderedSet<AccountId>
+    donatable_accounts: UnorderedSet<AccountId>,
+    reward_per_token: Balance,
+    undistributed_donations: Balance,
+    donation_accounts: LookupMap<AccountId, DonationAccount>,
+    bounties: Vector<Bounty>,
+    max_supply: Option<Balance>,
+    reports: UnorderedMap<u64, Vec<Report>>,
+    roles: LookupMap<Role, UnorderedSet<AccountId>>,
+    pending_owner: Option<AccountId>,
+    paused_features: Vec<Feature>,
+    credit_weighting: CreditWeighting,
+    credit_rules: CreditRules,
+    /// Block timestamp of the registration of every account registered by `storage_deposit`.
+    registered_at: LookupMap<AccountId, u64>,
+    verified_accounts: UnorderedSet<AccountId>,
+    rate_limits: RateLimits,
+    rate_limit_usage: LookupMap<AccountId, AccountUsage>,
+    verification_threshold: Option<VerificationThreshold>,
+    categories: UnorderedSet<String>,
+    deeds_by_category: LookupMap<String, UnorderedSet<u64>>,
+    deeds_by_tag: LookupMap<String, UnorderedSet<u64>>,
+    authored_deeds: DeedIndex,
+    credited_deeds: DeedIndex,
+    donation_tokens: UnorderedMap<AccountId, TokenPool>,
+    /// Donation accounts of every donated token, keyed by token and account.
+    token_donation_accounts: LookupMap<(AccountId, AccountId), DonationAccount>,
+    funding_rounds: Vector<FundingRound>,
+    donation_schedules: Vector<DonationSchedule>,
+    /// Ids of the schedules that still have installments to release.
+    active_donation_schedules: UnorderedSet<u64>,
+    donations: Vector<Donation>,
+    /// yoctoNEAR donated by every donor.
+    donor_totals: LookupMap<AccountId, Balance>,
+    /// The donors with the highest totals, highest first.
+    top_donors: Vec<(AccountId, Balance)>,
+    /// Deeds of the first version that `migrate_deeds` did not reach yet.
+    deed_backfill: DeedBackfill,
+    /// Part of the supply minted at initialization that the owner still holds. Reputation the
+    /// owner earned as author is not part of it.
+    owner_supply: Balance,
+    /// yoctoNEAR every donor deposited for the records of donations that attach no deposit.
+    record_storage: LookupMap<AccountId, Balance>
 }
 
 #[derive(BorshDeserialize, BorshSerialize)]
@@ -46,7 +219,24 @@ pub struct SocialDeed {
     title: String,
     description: String,
     proof: String,
-    creditors: UnorderedSet<AccountId>
+    creditors: UnorderedSet<AccountId>,
+    /// The weight of every credit that was not worth exactly one DEED.
+    credit_weights: LookupMap<AccountId, Balance>,
+    /// DEED minted to the author for the credits of this deed.
+    earned: Balance,
+    status: DeedStatus,
+    /// Block timestamp at which the deed was verified.
+    verified_at: Option<u64>,
+    category: Option<String>,
+    tags: Vec<String>,
+    /// Block timestamp at which the deed was posted.
+    created_at: u64,
+    /// Block timestamp of the last change of the texts or the status of the deed.
+    updated_at: u64,
+    /// Block timestamp of every credit.
+    credited_at: LookupMap<AccountId, u64>,
+    /// yoctoNEAR donated to the author for this deed.
+    donated: Balance
 }
 
 impl SocialDeed {
@@ -57,9 +247,97 @@ impl SocialDeed {
         description: String,
         proof: String
     ) -> Self{
-        let str_prefix = id.to_string();
-        let prefix = str_prefix.as_bytes();
-        Self { id, author, title, description, proof, creditors: UnorderedSet::new(prefix) }
+        Self {
+            id, author, title, description, proof,
+            creditors: UnorderedSet::new(StorageKey::DeedCreditors { deed_id: id }),
+            credit_weights: LookupMap::new(StorageKey::CreditWeights { deed_id: id }),
+            earned: 0,
+            status: DeedStatus::Active,
+            verified_at: None,
+            category: None,
+            tags: Vec::new(),
+            created_at: env::block_timestamp(),
+            updated_at: env::block_timestamp(),
+            credited_at: LookupMap::new(StorageKey::CreditTimestamps { deed_id: id }),
+            donated: 0
+        }
+    }
+
+    /// DEED the author earned with this deed.
+    pub fn earned_reputation(&self) -> Balance {
+        self.earned
+    }
+
+    /// Adds the credit of `creditor_id` that minted `weight` DEED.
+    fn add_credit(&mut self, creditor_id: &AccountId, weight: Balance) -> bool {
+        if !self.creditors.insert(creditor_id) {
+            return false;
+        }
+        if weight != 1 {
+            self.credit_weights.insert(creditor_id, &weight);
+        }
+        self.credited_at.insert(creditor_id, &env::block_timestamp());
+        self.earned += weight;
+        true
+    }
+
+    /// Removes the credit of `creditor_id` and returns the DEED it minted.
+    fn remove_credit(&mut self, creditor_id: &AccountId) -> Option<Balance> {
+        if !self.creditors.remove(creditor_id) {
+            return None;
+        }
+        let weight = self.credit_weights.remove(creditor_id).unwrap_or(1);
+        self.credited_at.remove(creditor_id);
+        self.earned -= weight;
+        Some(weight)
+    }
+
+    /// Whether the deed was neither retracted nor rejected. Verified deeds are still active.
+    pub fn is_active(&self) -> bool {
+        matches!(self.status, DeedStatus::Active | DeedStatus::Verified)
+    }
+
+    /// Panics if the deed was retracted or rejected.
+    pub fn assert_active(&self) {
+        assert!(self.is_active(), "The deed is not active anymore.");
+    }
+}
+
+/// Statuses are stored with their Borsh encoding, so new statuses are only ever appended.
+#[derive(BorshDeserialize, BorshSerialize, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+#[serde(rename_all = "snake_case")]
+pub enum DeedStatus {
+    Active,
+    /// Enough creditors vouched for the deed to reach the verification threshold.
+    Verified,
+    /// The author withdrew the deed. Its texts are removed and the DEED it earned is burned.
+    Retracted,
+    /// A moderator rejected the deed as fraudulent and slashed the DEED it earned.
+    Rejected,
+}
+
+/// A deed as it is persisted. `V1` is the layout of the first version, see `migration`, and `V2`
+/// the current `SocialDeed`. Deeds are upgraded to the current layout when they are read. A change
+/// of the fields of `SocialDeed` needs a frozen snapshot of the current layout and a new variant.
+#[derive(BorshDeserialize, BorshSerialize)]
+pub enum VersionedDeed {
+    V1(SocialDeedV1),
+    V2(SocialDeed),
+}
+
+impl From<VersionedDeed> for SocialDeed {
+    fn from(deed: VersionedDeed) -> Self {
+        match deed {
+            VersionedDeed::V1(deed) => deed.into(),
+            VersionedDeed::V2(deed) => deed,
+        }
+    }
+}
+
+impl From<SocialDeed> for VersionedDeed {
+    fn from(deed: SocialDeed) -> Self {
+        VersionedDeed::V2(deed)
     }
 }
 
@@ -72,20 +350,44 @@ pub struct SerializableDeed {
     description: String,
     proof: String,
     creditors: u64,
-    is_creditor: bool
+    is_creditor: bool,
+    status: DeedStatus,
+    verified_at: Option<U64>,
+    category: Option<String>,
+    tags: Vec<String>,
+    created_at: U64,
+    updated_at: U64,
+    /// Block timestamp at which the creditor credited the deed.
+    credited_at: Option<U64>,
+    donated: U128
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct DeedsPage {
+    deeds: Vec<SerializableDeed>,
+    next_cursor: Option<U64>
 }
 
 impl SerializableDeed {
-    pub fn new(
-        id: u64,
-        author: AccountId,
-        title: String,
-        description: String,
-        proof: String,
-        creditors: u64,
-        is_creditor: bool
-    ) -> Self{
-        Self { id, author, title, description, proof, creditors, is_creditor }
+    pub fn new(deed: SocialDeed, creditor_id: Option<&AccountId>) -> Self{
+        Self {
+            creditors: deed.creditors.len(),
+            is_creditor: creditor_id.is_some_and(|creditor_id| deed.creditors.contains(creditor_id)),
+            credited_at: creditor_id.and_then(|creditor_id| deed.credited_at.get(creditor_id)).map(U64::from),
+            created_at: deed.created_at.into(),
+            updated_at: deed.updated_at.into(),
+            donated: deed.donated.into(),
+            id: deed.id,
+            author: deed.author,
+            title: deed.title,
+            description: deed.description,
+            proof: deed.proof,
+            status: deed.status,
+            verified_at: deed.verified_at.map(U64::from),
+            category: deed.category,
+            tags: deed.tags
+        }
     }
 }
 
@@ -121,12 +423,28 @@ pub fn refund_deposit(storage_used: u64) {
     refund_deposit_to_account(storage_used, env::predecessor_account_id())
 }
 
+/// Settles the storage costs of a call that may have used or released storage since
+/// `initial_storage_usage`. Used storage is paid from the attached deposit like in
+/// `refund_deposit`, released storage is refunded to the predecessor together with the deposit.
+pub fn refund_storage_change(initial_storage_usage: u64) {
+    let final_storage_usage = env::storage_usage();
+    if final_storage_usage >= initial_storage_usage {
+        return refund_deposit(final_storage_usage - initial_storage_usage);
+    }
+    let released = env::storage_byte_cost() * Balance::from(initial_storage_usage - final_storage_usage);
+    let refund = env::attached_deposit() + released;
+    if refund > 1 {
+        Promise::new(env::predecessor_account_id()).transfer(refund);
+    }
+}
+
 const DATA_IMAGE_SVG_NEAR_ICON: &str = "data:image/svg+xml,%3C?xml version='1.0' encoding='utf-8'?%3E %3C!-- Svg Vector Icons : http://www.onlinewebfonts.com/icon --%3E %3C!DOCTYPE svg PUBLIC '-//W3C//DTD SVG 1.1//EN' 'http://www.w3.org/Graphics/SVG/1.1/DTD/svg11.dtd'%3E %3Csvg version='1.1' xmlns='http://www.w3.org/2000/svg' xmlns:xlink='http://www.w3.org/1999/xlink' x='0px' y='0px' viewBox='0 0 1000 1000' enable-background='new 0 0 1000 1000' xml:space='preserve'%3E %3Cmetadata%3E Svg Vector Icons : http://www.onlinewebfonts.com/icon %3C/metadata%3E %3Cg%3E%3Cg transform='translate(0.000000,511.000000) scale(0.100000,-0.100000)'%3E%3Cpath d='M4627.9,4997.8c-783.1-81.8-1539.6-415.1-2122.3-932.3c-472.3-419.1-848.5-977.3-1053-1564.1c-392.6-1128.6-241.3-2292,449.8-3451.3c300.6-503,697.2-1005.9,1543.7-1954.6c711.5-797.4,1220.6-1425.1,1443.5-1778.8c92-143.1,128.8-143.1,222.9,6.1c102.2,161.5,523.4,713.6,750.4,985.5c118.6,141.1,439.6,509.1,713.5,817.8c703.3,793.3,954.8,1095.9,1241.1,1494.6c707.4,989.6,1030.5,2040.5,922.1,3019.9c-184,1686.8-1441.4,3032.1-3103.7,3318.4C5361.9,5005.9,4881.4,5024.3,4627.9,4997.8z M4227.1,3073.8c206.5-42.9,433.4-169.7,609.3-341.4l161.5-157.4l165.6,159.5c253.5,241.3,535.7,361.9,848.5,361.9c639.9,0,1153.1-537.7,1155.2-1206.3c0-331.2-102.2-682.9-318.9-1104.1C6521.2,150,5961-424.5,5259.7-841.6c-120.6-71.6-237.2-130.9-261.7-130.9c-22.5,0-149.3,65.4-280.1,145.2C3785.5-263,3139.4,532.4,2894.1,1419.7c-71.6,253.5-71.6,642-2,856.7C3082.2,2853,3648.5,3192.4,4227.1,3073.8z'/%3E%3C/g%3E%3C/g%3E %3C/svg%3E";
 
 #[near_bindgen]
 impl Contract {
     /// Initializes the contract with the given total supply owned by the given `owner_id` with
-    /// default metadata (for example purposes only).
+    /// default metadata (for example purposes only). Reputation is minted when deeds are credited,
+    /// so the initial supply can be 0.
     #[init]
     pub fn new_default_meta(owner_id: AccountId, total_supply: U128) -> Self {
         Self::new(
@@ -155,20 +473,56 @@ impl Contract {
         assert!(!env::state_exists(), "Already initialized");
         metadata.assert_valid();
         let mut this = Self {
-            token: FungibleToken::new(b"a".to_vec()),
-            metadata: LazyOption::new(b"m".to_vec(), Some(&metadata)),
-            deeds: Vector::new(b"d".to_vec()),
+            token: FungibleToken::new(StorageKey::Token),
+            metadata: LazyOption::new(StorageKey::Metadata, Some(&metadata)),
+            deeds: Vector::new(StorageKey::VersionedDeeds),
             owner: owner_id.clone(),
-            donatable_accounts: UnorderedSet::new(b"s".to_vec())
+            donatable_accounts: UnorderedSet::new(StorageKey::DonatableAccounts),
+            reward_per_token: 0,
+            undistributed_donations: 0,
+            donation_accounts: LookupMap::new(StorageKey::DonationAccounts),
+            bounties: Vector::new(StorageKey::Bounties),
+            max_supply: None,
+            reports: UnorderedMap::new(StorageKey::Reports),
+            roles: LookupMap::new(StorageKey::Roles),
+            pending_owner: None,
+            paused_features: Vec::new(),
+            credit_weighting: CreditWeighting::Flat,
+            credit_rules: CreditRules::default(),
+            registered_at: LookupMap::new(StorageKey::RegisteredAt),
+            verified_accounts: UnorderedSet::new(StorageKey::VerifiedAccounts),
+            rate_limits: RateLimits::default(),
+            rate_limit_usage: LookupMap::new(StorageKey::RateLimitUsage),
+            verification_threshold: None,
+            categories: UnorderedSet::new(StorageKey::Categories),
+            deeds_by_category: LookupMap::new(StorageKey::DeedsByCategory),
+            deeds_by_tag: LookupMap::new(StorageKey::DeedsByTag),
+            authored_deeds: LookupMap::new(StorageKey::AuthoredDeeds),
+            credited_deeds: LookupMap::new(StorageKey::CreditedDeeds),
+            donation_tokens: UnorderedMap::new(StorageKey::DonationTokens),
+            token_donation_accounts: LookupMap::new(StorageKey::TokenDonationAccounts),
+            funding_rounds: Vector::new(StorageKey::FundingRounds),
+            donation_schedules: Vector::new(StorageKey::DonationSchedules),
+            active_donation_schedules: UnorderedSet::new(StorageKey::ActiveDonationSchedules),
+            donations: Vector::new(StorageKey::Donations),
+            donor_totals: LookupMap::new(StorageKey::DonorTotals),
+            top_donors: Vec::new(),
+            deed_backfill: DeedBackfill::default(),
+            owner_supply: total_supply.0,
+            record_storage: LookupMap::new(StorageKey::RecordStorage)
         };
+        // Registrations pay the storage of the donation accounts that settlements create.
+        this.token.account_storage_usage += this.measure_donation_storage_usage();
         this.token.internal_register_account(&owner_id);
-        this.token.internal_deposit(&owner_id, total_supply.into());
-        near_contract_standards::fungible_token::events::FtMint {
-            owner_id: &owner_id,
-            amount: &total_supply,
-            memo: Some("Initial tokens supply is minted"),
+        if total_supply.0 > 0 {
+            this.token.internal_deposit(&owner_id, total_supply.into());
+            near_contract_standards::fungible_token::events::FtMint {
+                owner_id: &owner_id,
+                amount: &total_supply,
+                memo: Some("Initial tokens supply is minted"),
+            }
+            .emit();
         }
-        .emit();
         this
     }
 
@@ -180,13 +534,27 @@ impl Contract {
     {
         let initial_storage_usage = env::storage_usage();
 
-        assert!(self.deeds.len() > id, "The id is out of range.");
-        let mut deed = self.deeds.get(id).unwrap();
+        self.assert_not_paused(Feature::Credits);
+        let mut deed = self.internal_get_deed(id);
+        deed.assert_active();
         assert_ne!(env::predecessor_account_id(), deed.author, "You cannot credit yourself.");
-        assert!(deed.creditors.insert(&env::predecessor_account_id()), "{} cannot credit the deed of {} again.", env::predecessor_account_id(), deed.author);
-        self.deeds.replace(id, &deed);
-        let memo = Some(format!("Social deed of {} credited by {}", deed.author, env::predecessor_account_id().to_string()));
-        self.token.internal_transfer(&self.owner, &deed.author, 1u128, memo);
+        self.assert_eligible_creditor(&env::predecessor_account_id());
+        self.internal_use_credit_quota(&env::predecessor_account_id());
+        let weight = self.credit_weight(&env::predecessor_account_id());
+        assert!(deed.add_credit(&env::predecessor_account_id(), weight), "{} cannot credit the deed of {} again.", env::predecessor_account_id(), deed.author);
+        self.internal_update_verification(&mut deed);
+        deed_index::index_credited_deed(&mut self.credited_deeds, &env::predecessor_account_id(), id);
+        let author = deed.author.clone();
+        self.internal_save_deed(deed);
+        let memo = format!("Social deed of {} credited by {}", author, env::predecessor_account_id());
+        self.internal_mint_reputation(&author, weight, &memo);
+        events::DeedCredited {
+            deed_id: id,
+            author: &author,
+            creditor: &env::predecessor_account_id(),
+            amount: &weight.into(),
+        }
+        .emit();
 
         refund_deposit(env::storage_usage() - initial_storage_usage);
     }
@@ -197,44 +565,159 @@ impl Contract {
         author: AccountId,
         title: String,
         description: String,
-        proof: String
+        proof: String,
+        category: Option<String>,
+        tags: Option<Vec<String>>
     )
     {
         let initial_storage_usage = env::storage_usage();
         
+        self.assert_not_paused(Feature::Deeds);
         assert_eq!(author, env::predecessor_account_id(), "The author must be the same as the calling account.");
-        self.deeds.push(&SocialDeed::new(self.deeds.len(), author.clone(), title, description, proof));
+        self.internal_use_deed_quota(&author);
+        let id = self.deeds.len();
+        let mut deed = SocialDeed::new(id, author.clone(), title, description, proof);
+        if let Some(category) = category {
+            self.assert_category(&category);
+            deed.category = Some(category);
+        }
+        deed.tags = categories::normalize_tags(tags.unwrap_or_default());
+        self.internal_index_deed(&deed);
+        deed_index::index_authored_deed(&mut self.authored_deeds, &author, id);
+        self.deeds.push(&deed.into());
         self.donatable_accounts.insert(&author);
+        events::DeedAdded { deed_id: id, author: &author }.emit();
 
         refund_deposit(env::storage_usage() - initial_storage_usage);
     }
 
+    /// Withdraws the credit the caller gave to the deed `id`. The DEED the credit minted is burned
+    /// from the author and the released storage is refunded to the caller.
+    pub fn uncredit(
+        &mut self,
+        id: u64
+    )
+    {
+        let initial_storage_usage = env::storage_usage();
+
+        self.assert_not_paused(Feature::Credits);
+        let mut deed = self.internal_get_deed(id);
+        deed.assert_active();
+        let creditor = env::predecessor_account_id();
+        let weight = deed.remove_credit(&creditor);
+        assert!(weight.is_some(), "{} did not credit the deed of {}.", creditor, deed.author);
+        self.internal_update_verification(&mut deed);
+        deed_index::unindex_credited_deed(&mut self.credited_deeds, &creditor, id);
+        let author = deed.author.clone();
+        self.internal_save_deed(deed);
+        let memo = format!("Credit of {} for the social deed of {} withdrawn", creditor, author);
+        let burned = self.internal_burn_reputation(&author, weight.unwrap(), &memo);
+        events::DeedUncredited {
+            deed_id: id,
+            author: &author,
+            creditor: &creditor,
+            amount: &burned.into(),
+        }
+        .emit();
+
+        refund_storage_change(initial_storage_usage);
+    }
+
+    /// Changes the texts, the category or the tags of the deed `id`. Only the author can update a
+    /// deed and only as long as nobody credited it. `None` keeps the current value.
     #[payable]
-    pub fn donate(
-        &mut self
+    pub fn update_deed(
+        &mut self,
+        id: u64,
+        title: Option<String>,
+        description: Option<String>,
+        proof: Option<String>,
+        category: Option<String>,
+        tags: Option<Vec<String>>
     )
     {
         let initial_storage_usage = env::storage_usage();
-        
-        let title = "Donation to all users".to_string();
-        let deposit = (env::attached_deposit() as f64)/(10u128.pow(24) as f64);
-        let description = format!("{} donated {} NEAR to all users. Thank you very much!", &env::predecessor_account_id(), deposit);
-        self.deeds.push(&SocialDeed::new(self.deeds.len(), env::predecessor_account_id(), title, description, "https://gifimage.net/wp-content/uploads/2017/10/donation-gif-10.gif".into()));
-        self.donatable_accounts.insert(&env::predecessor_account_id());
-
-        let remaining = calculate_and_check_deposit(env::storage_usage() - initial_storage_usage);
-        let minted_amount = self.token.total_supply - Into::<u128>::into(self.token.ft_balance_of(self.owner.clone())) - Into::<u128>::into(self.token.ft_balance_of(env::predecessor_account_id()));
-        for donatable in self.donatable_accounts.iter() {
-            if donatable == env::predecessor_account_id() {
-                continue;
+
+        self.assert_not_paused(Feature::Deeds);
+        let mut deed = self.internal_get_deed(id);
+        assert_eq!(deed.author, env::predecessor_account_id(), "Only the author can update the deed.");
+        deed.assert_active();
+        assert!(deed.creditors.is_empty(), "A credited deed cannot be updated anymore.");
+        if let Some(title) = title {
+            deed.title = title;
+        }
+        if let Some(description) = description {
+            deed.description = description;
+        }
+        if let Some(proof) = proof {
+            deed.proof = proof;
+        }
+        if category.is_some() || tags.is_some() {
+            self.internal_unindex_deed(&deed);
+            if let Some(category) = category {
+                self.assert_category(&category);
+                deed.category = Some(category);
             }
-            let share : u128 = ((Into::<u128>::into(self.token.ft_balance_of(donatable.clone())) as f64)/(minted_amount as f64) * (remaining as f64)) as u128;
-            if share > 10u128.pow(22){
-                let donation = (share as f64)/(10u128.pow(24) as f64);
-                env::log_str(format!("Donated {} NEAR to {}.", donation, donatable).as_str());
-                Promise::new(donatable).transfer(share);
+            if let Some(tags) = tags {
+                deed.tags = categories::normalize_tags(tags);
             }
+            self.internal_index_deed(&deed);
         }
+        deed.updated_at = env::block_timestamp();
+        let author = deed.author.clone();
+        self.internal_save_deed(deed);
+        events::DeedUpdated { deed_id: id, author: &author }.emit();
+
+        refund_storage_change(initial_storage_usage);
+    }
+
+    /// Retracts the deed `id` of the caller. The texts of the deed are removed and the released
+    /// storage is refunded. The DEED the deed earned is burned, the donations the author already
+    /// received with it are kept. Retracted deeds cannot be credited anymore and are hidden from
+    /// `social_deeds` by default.
+    #[payable]
+    pub fn retract_deed(
+        &mut self,
+        id: u64
+    )
+    {
+        let initial_storage_usage = env::storage_usage();
+
+        self.assert_not_paused(Feature::Deeds);
+        let deed = self.internal_get_deed(id);
+        assert_eq!(deed.author, env::predecessor_account_id(), "Only the author can retract the deed.");
+        deed.assert_active();
+        self.internal_retract_deed(deed);
+
+        refund_storage_change(initial_storage_usage);
+    }
+
+    /// Retracts `deed` and burns the reputation it earned.
+    pub(crate) fn internal_retract_deed(&mut self, mut deed: SocialDeed) {
+        let id = deed.id;
+        self.internal_unindex_deed(&deed);
+        deed.status = DeedStatus::Retracted;
+        deed.updated_at = env::block_timestamp();
+        deed.tags.clear();
+        deed.title.clear();
+        deed.description.clear();
+        deed.proof.clear();
+        let author = deed.author.clone();
+        let earned = deed.earned_reputation();
+        self.internal_save_deed(deed);
+        let burned = self.internal_burn_reputation(&author, earned, &format!("Social deed {} of {} retracted", id, author));
+        events::DeedRetracted { deed_id: id, author: &author, burned: &burned.into() }.emit();
+    }
+
+    pub(crate) fn internal_get_deed(&self, id: u64) -> SocialDeed {
+        assert!(self.deeds.len() > id, "The id is out of range.");
+        self.internal_get_untagged_deed(id)
+            .unwrap_or_else(|| self.deeds.get(id).unwrap().into())
+    }
+
+    pub(crate) fn internal_save_deed(&mut self, deed: SocialDeed) {
+        assert!(!self.deed_backfill.is_pending(deed.id), "The deed {} was not migrated yet.", deed.id);
+        self.deeds.replace(deed.id, &deed.into());
     }
 
     pub fn is_registered(self, account_id: AccountId) -> bool{
@@ -245,24 +728,45 @@ impl Contract {
         self.deeds.len()
     }
 
-    pub fn social_deeds(&self, creditor_id: AccountId, from_index: Option<U128>, limit: Option<u64>) -> Vec<SerializableDeed> {
-        let start_index: u128 = from_index.map(From::from).unwrap_or_default();
-        require!(
-            (self.deeds.len() as u128) > start_index,
-            "Out of bounds, please use a smaller from_index."
-        );
-        let limit = limit.map(|v| v as usize).unwrap_or(usize::MAX);
+    /// Scans up to `limit` deeds starting at the deed `from_index`, in ascending or `descending`
+    /// order of their ids, and returns those that pass the filters. Retracted deeds are skipped
+    /// unless `include_retracted` is set. Without `from_index` the scan starts at the first or the
+    /// last deed. Skipped deeds count against the limit, so a page can hold fewer deeds than
+    /// `limit` before the end is reached. `next_cursor` is the `from_index` of the next page and
+    /// `None` after the last page.
+    pub fn social_deeds(&self, creditor_id: AccountId, from_index: Option<U64>, limit: Option<u64>,
+                        include_retracted: Option<bool>, verified_only: Option<bool>,
+                        descending: Option<bool>) -> DeedsPage {
+        let limit = limit.unwrap_or(u64::MAX);
         require!(limit != 0, "Cannot provide limit of 0.");
-        self.deeds
-            .iter()
-            .skip(start_index as usize)
-            .take(limit)
-            .map(|deed| SerializableDeed::new(deed.id, deed.author, deed.title, deed.description, deed.proof, 
-                                                           deed.creditors.len(), deed.creditors.contains(&creditor_id)))
-            .collect()
+        let include_retracted = include_retracted.unwrap_or(false);
+        let verified_only = verified_only.unwrap_or(false);
+        let descending = descending.unwrap_or(false);
+        let len = self.deeds.len();
+        let mut cursor = if descending {
+            len.checked_sub(1).map(|last| from_index.map_or(last, |index| index.0.min(last)))
+        } else {
+            Some(from_index.map_or(0, |index| index.0)).filter(|index| *index < len)
+        };
+        let mut deeds = Vec::new();
+        let mut scanned = 0;
+        while let Some(id) = cursor {
+            if scanned == limit {
+                break;
+            }
+            scanned += 1;
+            cursor = if descending { id.checked_sub(1) } else { Some(id + 1).filter(|next| *next < len) };
+            let deed = self.internal_get_deed(id);
+            if (include_retracted || deed.status != DeedStatus::Retracted)
+                && (!verified_only || deed.status == DeedStatus::Verified) {
+                deeds.push(SerializableDeed::new(deed, Some(&creditor_id)));
+            }
+        }
+        DeedsPage { deeds, next_cursor: cursor.map(U64::from) }
     }
 
     fn on_account_closed(&mut self, account_id: AccountId, balance: Balance) {
+        self.registered_at.remove(&account_id);
         log!("Closed @{} with {}", account_id, balance);
     }
 
@@ -323,7 +827,47 @@ impl FungibleTokenResolver for Contract {
     }
 }
 
-near_contract_standards::impl_fungible_token_storage!(Contract, token, on_account_closed);
+#[near_bindgen]
+impl StorageManagement for Contract {
+    #[payable]
+    fn storage_deposit(
+        &mut self,
+        account_id: Option<AccountId>,
+        registration_only: Option<bool>,
+    ) -> StorageBalance {
+        self.assert_not_paused(Feature::Storage);
+        let registered_id = account_id.clone().unwrap_or_else(env::predecessor_account_id);
+        let is_new = !self.token.accounts.contains_key(&registered_id);
+        let balance = self.token.storage_deposit(account_id, registration_only);
+        if is_new {
+            self.internal_record_registration(&registered_id);
+        }
+        balance
+    }
+
+    #[payable]
+    fn storage_withdraw(&mut self, amount: Option<U128>) -> StorageBalance {
+        self.token.storage_withdraw(amount)
+    }
+
+    #[payable]
+    fn storage_unregister(&mut self, force: Option<bool>) -> bool {
+        if let Some((account_id, balance)) = self.token.internal_storage_unregister(force) {
+            self.on_account_closed(account_id, balance);
+            true
+        } else {
+            false
+        }
+    }
+
+    fn storage_balance_bounds(&self) -> StorageBalanceBounds {
+        self.token.storage_balance_bounds()
+    }
+
+    fn storage_balance_of(&self, account_id: AccountId) -> Option<StorageBalance> {
+        self.token.storage_balance_of(account_id)
+    }
+}
 
 #[near_bindgen]
 impl FungibleTokenMetadataProvider for Contract {
@@ -339,10 +883,11 @@ mod tests {
 
     use super::*;
 
-    const TOTAL_SUPPLY: Balance = 1_000_000_000_000_000;
-    const SAFE_STORAGE_COST: u128 = 10000000000000000000000;
+    pub(crate) const TOTAL_SUPPLY: Balance = 1_000_000_000_000_000;
+    pub(crate) const SAFE_STORAGE_COST: u128 = 100000000000000000000000;
+    pub(crate) const ONE_NEAR: Balance = 1_000_000_000_000_000_000_000_000;
 
-    fn get_context(predecessor_account_id: AccountId) -> VMContextBuilder {
+    pub(crate) fn get_context(predecessor_account_id: AccountId) -> VMContextBuilder {
         let mut builder = VMContextBuilder::new();
         builder
             .current_account_id(accounts(0))
@@ -351,11 +896,22 @@ mod tests {
         builder
     }
 
+    /// Sets up the next call of `account_id` with the attached `deposit`. The storage usage of the
+    /// earlier calls is kept, so the storage costs of the call can be calculated. Everything else,
+    /// like the block timestamp, is set on `context` beforehand.
+    pub(crate) fn call(context: &mut VMContextBuilder, account_id: AccountId, deposit: Balance) {
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(deposit)
+            .predecessor_account_id(account_id)
+            .build());
+    }
+
     #[test]
     fn test_new() {
         let mut context = get_context(accounts(1));
         testing_env!(context.build());
-        let contract = Contract::new_default_meta(accounts(1).into(), TOTAL_SUPPLY.into());
+        let contract = Contract::new_default_meta(accounts(1), TOTAL_SUPPLY.into());
         testing_env!(context.is_view(true).build());
         assert_eq!(contract.ft_total_supply().0, TOTAL_SUPPLY);
         assert_eq!(contract.ft_balance_of(accounts(1)).0, TOTAL_SUPPLY);
@@ -369,25 +925,41 @@ mod tests {
         let _contract = Contract::default();
     }
 
+    #[test]
+    fn test_storage_key_prefixes_are_fixed() {
+        fn prefix(key: StorageKey) -> Vec<u8> {
+            key.into_storage_key()
+        }
+        assert_eq!(prefix(StorageKey::Token), b"a");
+        assert_eq!(prefix(StorageKey::Bounties), b"b");
+        assert_eq!(prefix(StorageKey::VersionedDeeds), b"k\x05");
+        assert_eq!(prefix(StorageKey::DonorTotals), b"k\x16");
+        assert_eq!(prefix(StorageKey::DeedCreditors { deed_id: 1 }), b"k\x17\x01\0\0\0\0\0\0\0");
+        assert_eq!(prefix(StorageKey::BountySubmissions { bounty_id: 1 }), b"k\x18\x01\0\0\0\0\0\0\0");
+        assert_eq!(prefix(StorageKey::BountyWinners { bounty_id: 1 }), b"k\x19\x01\0\0\0\0\0\0\0");
+        assert_eq!(prefix(StorageKey::RoleMembers { role: Role::Moderator }), b"k\x1a\x02");
+        assert_eq!(prefix(StorageKey::CreditWeights { deed_id: 1 }), b"k\x1b\x01\0\0\0\0\0\0\0");
+        assert_eq!(prefix(StorageKey::CreditTimestamps { deed_id: 1 }), b"k\x1c\x01\0\0\0\0\0\0\0");
+        assert_eq!(prefix(StorageKey::CategoryDeeds { category: "art".into() }), b"k\x1d\x03\0\0\0art");
+        assert_eq!(prefix(StorageKey::TagDeeds { tag: "art".into() }), b"k\x1e\x03\0\0\0art");
+        assert_eq!(prefix(StorageKey::DeedsOfAuthor { account_id: "a.near".parse().unwrap() }), b"k\x1f\x06\0\0\0a.near");
+        assert_eq!(prefix(StorageKey::DeedsOfCreditor { account_id: "a.near".parse().unwrap() }), b"k\x20\x06\0\0\0a.near");
+        assert_eq!(prefix(StorageKey::RoundDeeds { round_id: 1 }), b"k\x21\x01\0\0\0\0\0\0\0");
+        assert_eq!(prefix(StorageKey::RoundDonors { round_id: 1 }), b"k\x22\x01\0\0\0\0\0\0\0");
+        assert_eq!(prefix(StorageKey::RecordStorage), b"k\x23");
+    }
+
     #[test]
     #[should_panic(expected = "This token is not transferable!")]
     fn test_transfer() {
         let mut context = get_context(accounts(2));
         testing_env!(context.build());
-        let mut contract = Contract::new_default_meta(accounts(2).into(), TOTAL_SUPPLY.into());
-        testing_env!(context
-            .storage_usage(env::storage_usage())
-            .attached_deposit(SAFE_STORAGE_COST)
-            .predecessor_account_id(accounts(1))
-            .build());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into());
+        call(&mut context, accounts(1), SAFE_STORAGE_COST);
         // Paying for account registration, aka storage deposit
         contract.storage_deposit(None, None);
 
-        testing_env!(context
-            .storage_usage(env::storage_usage())
-            .attached_deposit(1)
-            .predecessor_account_id(accounts(2))
-            .build());
+        call(&mut context, accounts(2), 1);
         let transfer_amount = TOTAL_SUPPLY / 3;
         contract.ft_transfer(accounts(1), transfer_amount.into(), None);
     }
@@ -397,16 +969,12 @@ mod tests {
     fn test_add_deed_panics_on_different_author() {
         let mut context = get_context(accounts(2));
         testing_env!(context.build());
-        let mut contract = Contract::new_default_meta(accounts(2).into(), TOTAL_SUPPLY.into());
-        testing_env!(context
-            .storage_usage(env::storage_usage())
-            .attached_deposit(SAFE_STORAGE_COST)
-            .predecessor_account_id(accounts(1))
-            .build());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into());
+        call(&mut context, accounts(1), SAFE_STORAGE_COST);
         // Paying for account registration, aka storage deposit
         contract.storage_deposit(None, None);
 
-        contract.add_deed(accounts(2), "".into(), "".into(), "".into());
+        contract.add_deed(accounts(2), "".into(), "".into(), "".into(), None, None);
     }
 
     #[test]
@@ -414,40 +982,28 @@ mod tests {
     fn test_add_deed_panics_without_attached_deposit() {
         let mut context = get_context(accounts(2));
         testing_env!(context.build());
-        let mut contract = Contract::new_default_meta(accounts(2).into(), TOTAL_SUPPLY.into());
-        testing_env!(context
-            .storage_usage(env::storage_usage())
-            .attached_deposit(SAFE_STORAGE_COST)
-            .predecessor_account_id(accounts(1))
-            .build());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into());
+        call(&mut context, accounts(1), SAFE_STORAGE_COST);
         // Paying for account registration, aka storage deposit
         contract.storage_deposit(None, None);
         
-        testing_env!(context
-            .storage_usage(env::storage_usage())
-            .attached_deposit(0)
-            .predecessor_account_id(accounts(1))
-            .build());
+        call(&mut context, accounts(1), 0);
 
-        contract.add_deed(accounts(1), "".into(), "".into(), "".into());
+        contract.add_deed(accounts(1), "".into(), "".into(), "".into(), None, None);
     }
 
     #[test]
     fn test_add_deed_adds_all_data() {
         let mut context = get_context(accounts(2));
         testing_env!(context.build());
-        let mut contract = Contract::new_default_meta(accounts(2).into(), TOTAL_SUPPLY.into());
-        testing_env!(context
-            .storage_usage(env::storage_usage())
-            .attached_deposit(SAFE_STORAGE_COST)
-            .predecessor_account_id(accounts(1))
-            .build());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into());
+        call(&mut context, accounts(1), SAFE_STORAGE_COST);
         // Paying for account registration, aka storage deposit
         contract.storage_deposit(None, None);
 
-        contract.add_deed(accounts(1), "title".into(), "description".into(), "proof".into());
+        contract.add_deed(accounts(1), "title".into(), "description".into(), "proof".into(), None, None);
 
-        let deed = contract.deeds.get(0).unwrap();
+        let deed = contract.internal_get_deed(0);
         assert_eq!(deed.author, accounts(1));
         assert_eq!(deed.title, "title");
         assert_eq!(deed.description, "description");
@@ -458,22 +1014,18 @@ mod tests {
     fn test_add_deed_sets_id_correctly() {
         let mut context = get_context(accounts(2));
         testing_env!(context.build());
-        let mut contract = Contract::new_default_meta(accounts(2).into(), TOTAL_SUPPLY.into());
-        testing_env!(context
-            .storage_usage(env::storage_usage())
-            .attached_deposit(SAFE_STORAGE_COST)
-            .predecessor_account_id(accounts(1))
-            .build());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into());
+        call(&mut context, accounts(1), SAFE_STORAGE_COST);
         // Paying for account registration, aka storage deposit
         contract.storage_deposit(None, None);
 
-        contract.add_deed(accounts(1), "title".into(), "description".into(), "proof".into());
-        contract.add_deed(accounts(1), "title".into(), "description".into(), "proof".into());
+        contract.add_deed(accounts(1), "title".into(), "description".into(), "proof".into(), None, None);
+        contract.add_deed(accounts(1), "title".into(), "description".into(), "proof".into(), None, None);
 
-        let deed = contract.deeds.get(0).unwrap();
+        let deed = contract.internal_get_deed(0);
         assert_eq!(deed.id, 0u64);
 
-        let deed = contract.deeds.get(1).unwrap();
+        let deed = contract.internal_get_deed(1);
         assert_eq!(deed.id, 1u64);
     }
 
@@ -482,21 +1034,13 @@ mod tests {
     fn cannot_credit_deed_twice() {
         let mut context = get_context(accounts(2));
         testing_env!(context.build());
-        let mut contract = Contract::new_default_meta(accounts(2).into(), TOTAL_SUPPLY.into());
-        testing_env!(context
-            .storage_usage(env::storage_usage())
-            .attached_deposit(SAFE_STORAGE_COST)
-            .predecessor_account_id(accounts(1))
-            .build());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into());
+        call(&mut context, accounts(1), SAFE_STORAGE_COST);
         // Paying for account registration, aka storage deposit
         contract.storage_deposit(None, None);
-        contract.add_deed(accounts(1), "title".into(), "description".into(), "proof".into());
+        contract.add_deed(accounts(1), "title".into(), "description".into(), "proof".into(), None, None);
 
-        testing_env!(context
-            .storage_usage(env::storage_usage())
-            .attached_deposit(SAFE_STORAGE_COST)
-            .predecessor_account_id(accounts(3))
-            .build());
+        call(&mut context, accounts(3), SAFE_STORAGE_COST);
         // Paying for account registration, aka storage deposit
         contract.storage_deposit(None, None);
         contract.credit(0);
@@ -508,38 +1052,26 @@ mod tests {
     fn cannot_credit_own_deed() {
         let mut context = get_context(accounts(2));
         testing_env!(context.build());
-        let mut contract = Contract::new_default_meta(accounts(2).into(), TOTAL_SUPPLY.into());
-        testing_env!(context
-            .storage_usage(env::storage_usage())
-            .attached_deposit(SAFE_STORAGE_COST)
-            .predecessor_account_id(accounts(1))
-            .build());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into());
+        call(&mut context, accounts(1), SAFE_STORAGE_COST);
         // Paying for account registration, aka storage deposit
         contract.storage_deposit(None, None);
-        contract.add_deed(accounts(1), "title".into(), "description".into(), "proof".into());
+        contract.add_deed(accounts(1), "title".into(), "description".into(), "proof".into(), None, None);
         
         contract.credit(0);
     }
 
     #[test]
-    fn test_credit_transfers_token_to_author() {
+    fn test_credit_mints_token_to_author() {
         let mut context = get_context(accounts(2));
         testing_env!(context.build());
-        let mut contract = Contract::new_default_meta(accounts(2).into(), TOTAL_SUPPLY.into());
-        testing_env!(context
-            .storage_usage(env::storage_usage())
-            .attached_deposit(SAFE_STORAGE_COST)
-            .predecessor_account_id(accounts(1))
-            .build());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into());
+        call(&mut context, accounts(1), SAFE_STORAGE_COST);
         // Paying for account registration, aka storage deposit
         contract.storage_deposit(None, None);
-        contract.add_deed(accounts(1), "title".into(), "description".into(), "proof".into());
+        contract.add_deed(accounts(1), "title".into(), "description".into(), "proof".into(), None, None);
 
-        testing_env!(context
-            .storage_usage(env::storage_usage())
-            .attached_deposit(SAFE_STORAGE_COST)
-            .predecessor_account_id(accounts(3))
-            .build());
+        call(&mut context, accounts(3), SAFE_STORAGE_COST);
         // Paying for account registration, aka storage deposit
         contract.storage_deposit(None, None);
         contract.credit(0);
@@ -550,46 +1082,59 @@ mod tests {
             .is_view(true)
             .attached_deposit(0)
             .build());
-        assert_eq!(contract.ft_balance_of(accounts(2)).0, (TOTAL_SUPPLY - 1));
+        assert_eq!(contract.ft_balance_of(accounts(2)).0, TOTAL_SUPPLY);
+        assert_eq!(contract.ft_total_supply().0, TOTAL_SUPPLY + 1);
         assert_eq!(contract.ft_balance_of(accounts(1)).0, 1);
         assert_eq!(contract.ft_balance_of(accounts(3)).0, 0);
     }
     
 
+    #[test]
+    fn test_credit_emits_events() {
+        let mut context = get_context(accounts(2));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into());
+        call(&mut context, accounts(1), SAFE_STORAGE_COST);
+        // Paying for account registration, aka storage deposit
+        contract.storage_deposit(None, None);
+        contract.add_deed(accounts(1), "title".into(), "description".into(), "proof".into(), None, None);
+
+        call(&mut context, accounts(3), SAFE_STORAGE_COST);
+        contract.credit(0);
+
+        assert_eq!(get_logs(), [
+            r#"EVENT_JSON:{"standard":"nep141","version":"1.0.0","event":"ft_mint","data":[{"owner_id":"bob","amount":"1","memo":"Social deed of bob credited by danny"}]}"#,
+            r#"EVENT_JSON:{"standard":"social_bounty","version":"1.0.0","event":"deed_credited","data":[{"deed_id":0,"author":"bob","creditor":"danny","amount":"1"}]}"#
+        ]);
+    }
+
     #[test]
     fn test_donation_donated_to_single_account() {
         let mut context = get_context(accounts(2));
         testing_env!(context.build());
-        let mut contract = Contract::new_default_meta(accounts(2).into(), TOTAL_SUPPLY.into());
-        testing_env!(context
-            .storage_usage(env::storage_usage())
-            .attached_deposit(SAFE_STORAGE_COST)
-            .predecessor_account_id(accounts(1))
-            .build());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into());
+        call(&mut context, accounts(1), SAFE_STORAGE_COST);
         // Paying for account registration, aka storage deposit
         contract.storage_deposit(None, None);
-        contract.add_deed(accounts(1), "title".into(), "description".into(), "proof".into());
+        contract.add_deed(accounts(1), "title".into(), "description".into(), "proof".into(), None, None);
 
-        testing_env!(context
-            .storage_usage(env::storage_usage())
-            .attached_deposit(SAFE_STORAGE_COST)
-            .predecessor_account_id(accounts(3))
-            .build());
+        call(&mut context, accounts(3), SAFE_STORAGE_COST);
         // Paying for account registration, aka storage deposit
         contract.storage_deposit(None, None);
         contract.credit(0);
 
         
-        testing_env!(context
-            .storage_usage(env::storage_usage())
-            .attached_deposit(10u128.pow(24))
-            .predecessor_account_id(accounts(4))
-            .build());
+        call(&mut context, accounts(4), 10u128.pow(24));
         // Paying for account registration, aka storage deposit
         contract.storage_deposit(None, None);
+        let initial_storage_usage = env::storage_usage();
         contract.donate();
+        let remaining = 10u128.pow(24) - env::storage_byte_cost() * Balance::from(env::storage_usage() - initial_storage_usage);
         
-        assert_eq!(get_logs(), ["Donated 0.9963700000000001 NEAR to bob."], "Expected a donation log.");
+        assert_eq!(get_logs(), [format!(r#"EVENT_JSON:{{"standard":"social_bounty","version":"1.0.0","event":"donation_received","data":[{{"donor":"eugene","amount":"{}"}}]}}"#, remaining)], "Expected a donation event.");
+        assert_eq!(contract.claimable_donations(accounts(1)).0, remaining);
+        assert_eq!(contract.claimable_donations(accounts(3)).0, 0);
+        assert_eq!(contract.claimable_donations(accounts(4)).0, 0);
     }
     
 
@@ -597,47 +1142,36 @@ mod tests {
     fn test_donation_donated_to_two_accounts() {
         let mut context = get_context(accounts(2));
         testing_env!(context.build());
-        let mut contract = Contract::new_default_meta(accounts(2).into(), TOTAL_SUPPLY.into());
-        testing_env!(context
-            .storage_usage(env::storage_usage())
-            .attached_deposit(SAFE_STORAGE_COST)
-            .predecessor_account_id(accounts(1))
-            .build());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into());
+        call(&mut context, accounts(1), SAFE_STORAGE_COST);
         // Paying for account registration, aka storage deposit
         contract.storage_deposit(None, None);
-        contract.add_deed(accounts(1), "title".into(), "description".into(), "proof".into());
+        contract.add_deed(accounts(1), "title".into(), "description".into(), "proof".into(), None, None);
 
-        testing_env!(context
-            .storage_usage(env::storage_usage())
-            .attached_deposit(SAFE_STORAGE_COST)
-            .predecessor_account_id(accounts(5))
-            .build());
+        call(&mut context, accounts(5), SAFE_STORAGE_COST);
         // Paying for account registration, aka storage deposit
         contract.storage_deposit(None, None);
-        contract.add_deed(accounts(5), "title".into(), "description".into(), "proof".into());
+        contract.add_deed(accounts(5), "title".into(), "description".into(), "proof".into(), None, None);
         contract.credit(0);
 
-        testing_env!(context
-            .storage_usage(env::storage_usage())
-            .attached_deposit(SAFE_STORAGE_COST)
-            .predecessor_account_id(accounts(3))
-            .build());
+        call(&mut context, accounts(3), SAFE_STORAGE_COST);
         // Paying for account registration, aka storage deposit
         contract.storage_deposit(None, None);
         contract.credit(0);
         contract.credit(1);
 
         
-        testing_env!(context
-            .storage_usage(env::storage_usage())
-            .attached_deposit(10u128.pow(24))
-            .predecessor_account_id(accounts(4))
-            .build());
+        call(&mut context, accounts(4), 10u128.pow(24));
         // Paying for account registration, aka storage deposit
         contract.storage_deposit(None, None);
+        let initial_storage_usage = env::storage_usage();
         contract.donate();
+        let remaining = 10u128.pow(24) - env::storage_byte_cost() * Balance::from(env::storage_usage() - initial_storage_usage);
         
-        assert_eq!(get_logs(), ["Donated 0.6642466666666667 NEAR to bob.", "Donated 0.3321233333333333 NEAR to fargo."], "Expected a donation log.");
+        assert_eq!(get_logs(), [format!(r#"EVENT_JSON:{{"standard":"social_bounty","version":"1.0.0","event":"donation_received","data":[{{"donor":"eugene","amount":"{}"}}]}}"#, remaining)], "Expected a donation event.");
+        assert_eq!(contract.claimable_donations(accounts(1)).0, remaining / 3 * 2);
+        assert_eq!(contract.claimable_donations(accounts(5)).0, remaining / 3);
+        assert_eq!(contract.undistributed_donations().0, remaining % 3);
     }
     
 
@@ -645,47 +1179,221 @@ mod tests {
     fn test_creditors_calculation() {
         let mut context = get_context(accounts(2));
         testing_env!(context.build());
-        let mut contract = Contract::new_default_meta(accounts(2).into(), TOTAL_SUPPLY.into());
-        testing_env!(context
-            .storage_usage(env::storage_usage())
-            .attached_deposit(SAFE_STORAGE_COST)
-            .predecessor_account_id(accounts(1))
-            .build());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into());
+        call(&mut context, accounts(1), SAFE_STORAGE_COST);
         // Paying for account registration, aka storage deposit
         contract.storage_deposit(None, None);
-        contract.add_deed(accounts(1), "title".into(), "description".into(), "proof".into());
+        contract.add_deed(accounts(1), "title".into(), "description".into(), "proof".into(), None, None);
 
-        testing_env!(context
-            .storage_usage(env::storage_usage())
-            .attached_deposit(SAFE_STORAGE_COST)
-            .predecessor_account_id(accounts(5))
-            .build());
+        call(&mut context, accounts(5), SAFE_STORAGE_COST);
         // Paying for account registration, aka storage deposit
         contract.storage_deposit(None, None);
-        contract.add_deed(accounts(5), "title".into(), "description".into(), "proof".into());
+        contract.add_deed(accounts(5), "title".into(), "description".into(), "proof".into(), None, None);
         contract.credit(0);
 
+        call(&mut context, accounts(3), SAFE_STORAGE_COST);
+        // Paying for account registration, aka storage deposit
+        contract.storage_deposit(None, None);
+        contract.credit(0);
+        contract.credit(1);
+
         testing_env!(context
             .storage_usage(env::storage_usage())
-            .attached_deposit(SAFE_STORAGE_COST)
-            .predecessor_account_id(accounts(3))
+            .account_balance(env::account_balance())
+            .is_view(true)
+            .attached_deposit(0)
             .build());
+        let deeds = contract.social_deeds(accounts(5), None, Some(2u64), None, None, None).deeds;
+        assert_eq!(deeds[0].creditors, 2, "creditors should be counted correctly.");
+        assert!(deeds[0].is_creditor);
+        assert_eq!(deeds[1].creditors, 1, "creditors should be counted correctly.");
+        assert!(!deeds[1].is_creditor);
+    }
+
+    #[test]
+    fn test_social_deeds_pages() {
+        let mut context = get_context(accounts(2));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into());
+        let page = contract.social_deeds(accounts(1), None, None, None, None, None);
+        assert!(page.deeds.is_empty());
+        assert!(page.next_cursor.is_none());
+        let page = contract.social_deeds(accounts(1), None, Some(3), None, None, Some(true));
+        assert!(page.deeds.is_empty());
+
+        call(&mut context, accounts(1), SAFE_STORAGE_COST);
+        for _ in 0..5 {
+            contract.add_deed(accounts(1), "title".into(), "description".into(), "proof".into(), None, None);
+        }
+        contract.retract_deed(3);
+
+        let ids = |page: &DeedsPage| page.deeds.iter().map(|deed| deed.id).collect::<Vec<_>>();
+        let page = contract.social_deeds(accounts(1), None, Some(2), None, None, None);
+        assert_eq!(ids(&page), vec![0, 1]);
+        assert_eq!(page.next_cursor.map(|cursor| cursor.0), Some(2));
+        let page = contract.social_deeds(accounts(1), page.next_cursor, Some(2), None, None, None);
+        assert_eq!(ids(&page), vec![2]);
+        assert_eq!(page.next_cursor.map(|cursor| cursor.0), Some(4));
+        let page = contract.social_deeds(accounts(1), page.next_cursor, Some(2), None, None, None);
+        assert_eq!(ids(&page), vec![4]);
+        assert!(page.next_cursor.is_none());
+        let page = contract.social_deeds(accounts(1), Some(5.into()), None, None, None, None);
+        assert!(page.deeds.is_empty());
+
+        let page = contract.social_deeds(accounts(1), None, Some(2), None, None, Some(true));
+        assert_eq!(ids(&page), vec![4]);
+        assert_eq!(page.next_cursor.map(|cursor| cursor.0), Some(2));
+        let page = contract.social_deeds(accounts(1), page.next_cursor, None, None, None, Some(true));
+        assert_eq!(ids(&page), vec![2, 1, 0]);
+        assert!(page.next_cursor.is_none());
+        let page = contract.social_deeds(accounts(1), Some(100.into()), Some(1), Some(true), None, Some(true));
+        assert_eq!(ids(&page), vec![4]);
+    }
+
+    #[test]
+    fn test_update_deed_before_credit() {
+        let mut context = get_context(accounts(2));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into());
+        call(&mut context, accounts(1), SAFE_STORAGE_COST);
+        // Paying for account registration, aka storage deposit
+        contract.storage_deposit(None, None);
+        contract.add_deed(accounts(1), "title".into(), "description".into(), "proof".into(), None, None);
+
+        contract.update_deed(0, Some("new title".into()), None, Some("new proof".into()), None, None);
+
+        let deed = contract.internal_get_deed(0);
+        assert_eq!(deed.title, "new title");
+        assert_eq!(deed.description, "description");
+        assert_eq!(deed.proof, "new proof");
+    }
+
+    #[test]
+    #[should_panic(expected = "A credited deed cannot be updated anymore.")]
+    fn cannot_update_credited_deed() {
+        let mut context = get_context(accounts(2));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into());
+        call(&mut context, accounts(1), SAFE_STORAGE_COST);
         // Paying for account registration, aka storage deposit
         contract.storage_deposit(None, None);
+        contract.add_deed(accounts(1), "title".into(), "description".into(), "proof".into(), None, None);
+
+        call(&mut context, accounts(3), SAFE_STORAGE_COST);
+        contract.credit(0);
+
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        contract.update_deed(0, Some("new title".into()), None, None, None, None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the author can update the deed.")]
+    fn cannot_update_deed_of_other_author() {
+        let mut context = get_context(accounts(2));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into());
+        call(&mut context, accounts(1), SAFE_STORAGE_COST);
+        // Paying for account registration, aka storage deposit
+        contract.storage_deposit(None, None);
+        contract.add_deed(accounts(1), "title".into(), "description".into(), "proof".into(), None, None);
+
+        testing_env!(context.predecessor_account_id(accounts(3)).build());
+        contract.update_deed(0, Some("new title".into()), None, None, None, None);
+    }
+
+    #[test]
+    fn test_retract_deed_burns_reputation_and_hides_deed() {
+        let mut context = get_context(accounts(2));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into());
+        call(&mut context, accounts(1), SAFE_STORAGE_COST);
+        // Paying for account registration, aka storage deposit
+        contract.storage_deposit(None, None);
+        contract.add_deed(accounts(1), "title".into(), "description".into(), "proof".into(), None, None);
+        contract.add_deed(accounts(1), "title".into(), "description".into(), "proof".into(), None, None);
+
+        call(&mut context, accounts(3), SAFE_STORAGE_COST);
         contract.credit(0);
         contract.credit(1);
 
+        call(&mut context, accounts(1), 0);
+        let initial_storage_usage = env::storage_usage();
+        contract.retract_deed(0);
+        assert!(env::storage_usage() < initial_storage_usage, "Retracting should release storage.");
+
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, 1);
+        assert_eq!(contract.ft_total_supply().0, TOTAL_SUPPLY + 1);
+        let deeds = contract.social_deeds(accounts(3), None, None, None, None, None).deeds;
+        assert_eq!(deeds.len(), 1);
+        assert_eq!(deeds[0].id, 1);
+        let deeds = contract.social_deeds(accounts(3), None, None, Some(true), None, None).deeds;
+        assert_eq!(deeds.len(), 2);
+        assert_eq!(deeds[0].status, DeedStatus::Retracted);
+        assert_eq!(deeds[0].title, "");
+    }
+
+    #[test]
+    #[should_panic(expected = "The deed is not active anymore.")]
+    fn cannot_credit_retracted_deed() {
+        let mut context = get_context(accounts(2));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into());
+        call(&mut context, accounts(1), SAFE_STORAGE_COST);
+        // Paying for account registration, aka storage deposit
+        contract.storage_deposit(None, None);
+        contract.add_deed(accounts(1), "title".into(), "description".into(), "proof".into(), None, None);
+        contract.retract_deed(0);
+
+        call(&mut context, accounts(3), SAFE_STORAGE_COST);
+        contract.credit(0);
+    }
+
+    #[test]
+    fn test_uncredit_burns_token_of_author() {
+        let mut context = get_context(accounts(2));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into());
+        call(&mut context, accounts(1), SAFE_STORAGE_COST);
+        // Paying for account registration, aka storage deposit
+        contract.storage_deposit(None, None);
+        contract.add_deed(accounts(1), "title".into(), "description".into(), "proof".into(), None, None);
+
+        call(&mut context, accounts(3), SAFE_STORAGE_COST);
+        contract.credit(0);
+
         testing_env!(context
             .storage_usage(env::storage_usage())
-            .account_balance(env::account_balance())
-            .is_view(true)
             .attached_deposit(0)
             .build());
+        let initial_storage_usage = env::storage_usage();
+        contract.uncredit(0);
+        assert!(env::storage_usage() < initial_storage_usage, "Uncrediting should release storage.");
+        assert_eq!(get_logs()[1], r#"EVENT_JSON:{"standard":"social_bounty","version":"1.0.0","event":"deed_uncredited","data":[{"deed_id":0,"author":"bob","creditor":"danny","amount":"1"}]}"#);
+
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, 0);
+        assert_eq!(contract.internal_get_deed(0).creditors.len(), 0);
+
+        // The deed can be credited again.
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(SAFE_STORAGE_COST)
+            .build());
+        contract.credit(0);
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "danny did not credit the deed of bob.")]
+    fn cannot_uncredit_without_credit() {
+        let mut context = get_context(accounts(2));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into());
+        call(&mut context, accounts(1), SAFE_STORAGE_COST);
         // Paying for account registration, aka storage deposit
-        let deeds = contract.social_deeds(accounts(5), None, Some(2u64));
-        let deed = deeds.first().unwrap();
-        
-        //This is always 0 - probabaly a mistake on my side
-        assert_eq!(deed.creditors, 2, "creditors should be counted correctly.");
+        contract.storage_deposit(None, None);
+        contract.add_deed(accounts(1), "title".into(), "description".into(), "proof".into(), None, None);
+
+        testing_env!(context.predecessor_account_id(accounts(3)).build());
+        contract.uncredit(0);
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,410 @@
+//! Migration of the state of the first released version of the contract.
+//!
+//! New code is deployed with `upgrade`, which calls `migrate` right after the deployment.
+//! `migrate` converts the state of the first version to the current `Contract`. Its deeds keep
+//! their storage and layout until `migrate_deeds` reaches them in batches afterwards.
+
+use crate::*;
+use near_sdk::Gas;
+
+/// Title and proof of the deeds that earlier versions posted for every donation to all users.
+const DONATION_DEED_TITLE: &str = "Donation to all users";
+const DONATION_DEED_PROOF: &str = "https://gifimage.net/wp-content/uploads/2017/10/donation-gif-10.gif";
+
+/// Gas reserved for `upgrade` itself, everything else is attached to the call of `migrate`.
+const GAS_FOR_UPGRADE: Gas = Gas(20_000_000_000_000);
+
+/// The deed of the first version of the contract. The creditors use the decimal id as prefix.
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct SocialDeedV1 {
+    id: u64,
+    author: AccountId,
+    title: String,
+    description: String,
+    proof: String,
+    creditors: UnorderedSet<AccountId>
+}
+
+impl From<SocialDeedV1> for SocialDeed {
+    fn from(deed: SocialDeedV1) -> Self {
+        let SocialDeedV1 { id, author, title, description, proof, creditors } = deed;
+        Self {
+            id, author, title, description, proof,
+            credit_weights: LookupMap::new(StorageKey::CreditWeights { deed_id: id }),
+            // Every credit minted one DEED in the first version.
+            earned: Balance::from(creditors.len()),
+            creditors,
+            status: DeedStatus::Active,
+            verified_at: None,
+            category: None,
+            tags: Vec::new(),
+            // The first version did not record any timestamps.
+            created_at: 0,
+            updated_at: 0,
+            credited_at: LookupMap::new(StorageKey::CreditTimestamps { deed_id: id }),
+            donated: 0
+        }
+    }
+}
+
+/// The state of the first version of the contract.
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct ContractV1 {
+    token: FungibleToken,
+    metadata: LazyOption<FungibleTokenMetadata>,
+    deeds: Vector<SocialDeedV1>,
+    owner: AccountId,
+    donatable_accounts: UnorderedSet<AccountId>
+}
+
+/// The deeds of the first version that `migrate_deeds` did not reach yet. They are still stored
+/// without the tag of `VersionedDeed` and lack the entries of the author and creditor indexes.
+#[derive(BorshDeserialize, BorshSerialize, Default)]
+pub struct DeedBackfill {
+    /// Id of the next deed to migrate.
+    next: u64,
+    /// Id after the last deed to migrate.
+    end: u64
+}
+
+impl DeedBackfill {
+    pub fn is_pending(&self, id: u64) -> bool {
+        (self.next..self.end).contains(&id)
+    }
+}
+
+impl From<ContractV1> for Contract {
+    fn from(old: ContractV1) -> Self {
+        let ContractV1 { token, metadata, deeds, owner, donatable_accounts } = old;
+        // The first version did not tell the pre-minted supply apart from reputation the owner
+        // earned, so the whole balance of the owner counts as pre-minted.
+        let owner_supply = token.accounts.get(&owner).unwrap_or(0);
+        let deed_backfill = DeedBackfill { next: 0, end: deeds.len() };
+        Self {
+            token,
+            metadata,
+            // A vector only stores its length and prefix, so the deeds stay where they are and
+            // are read untagged until `migrate_deeds` reaches them.
+            deeds: Vector::try_from_slice(&deeds.try_to_vec().unwrap()).unwrap(),
+            owner,
+            donatable_accounts,
+            reward_per_token: 0,
+            undistributed_donations: 0,
+            donation_accounts: LookupMap::new(StorageKey::DonationAccounts),
+            bounties: Vector::new(StorageKey::Bounties),
+            max_supply: None,
+            reports: UnorderedMap::new(StorageKey::Reports),
+            roles: LookupMap::new(StorageKey::Roles),
+            pending_owner: None,
+            paused_features: Vec::new(),
+            credit_weighting: CreditWeighting::Flat,
+            credit_rules: CreditRules::default(),
+            registered_at: LookupMap::new(StorageKey::RegisteredAt),
+            verified_accounts: UnorderedSet::new(StorageKey::VerifiedAccounts),
+            rate_limits: RateLimits::default(),
+            rate_limit_usage: LookupMap::new(StorageKey::RateLimitUsage),
+            verification_threshold: None,
+            categories: UnorderedSet::new(StorageKey::Categories),
+            deeds_by_category: LookupMap::new(StorageKey::DeedsByCategory),
+            deeds_by_tag: LookupMap::new(StorageKey::DeedsByTag),
+            authored_deeds: LookupMap::new(StorageKey::AuthoredDeeds),
+            credited_deeds: LookupMap::new(StorageKey::CreditedDeeds),
+            donation_tokens: UnorderedMap::new(StorageKey::DonationTokens),
+            token_donation_accounts: LookupMap::new(StorageKey::TokenDonationAccounts),
+            funding_rounds: Vector::new(StorageKey::FundingRounds),
+            donation_schedules: Vector::new(StorageKey::DonationSchedules),
+            active_donation_schedules: UnorderedSet::new(StorageKey::ActiveDonationSchedules),
+            donations: Vector::new(StorageKey::Donations),
+            donor_totals: LookupMap::new(StorageKey::DonorTotals),
+            top_donors: Vec::new(),
+            deed_backfill,
+            owner_supply,
+            record_storage: LookupMap::new(StorageKey::RecordStorage)
+        }
+    }
+}
+
+impl Contract {
+    /// Reads the deed `id` if it is still stored the way the first version stored it.
+    pub(crate) fn internal_get_untagged_deed(&self, id: u64) -> Option<SocialDeed> {
+        self.deed_backfill.is_pending(id).then(|| {
+            SocialDeedV1::try_from_slice(&self.deeds.get_raw(id).unwrap()).unwrap().into()
+        })
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Converts the state of the first version to the current layout. Called by `upgrade` after
+    /// the new code was deployed.
+    #[private]
+    #[init(ignore_state)]
+    pub fn migrate() -> Self {
+        let old: ContractV1 = env::state_read().expect("The contract is not initialized");
+        let mut contract = Contract::from(old);
+        // Registrations from now on pay the storage of the donation accounts as well.
+        contract.token.account_storage_usage += contract.measure_donation_storage_usage();
+        contract
+    }
+
+    /// Deploys the wasm code passed as input of the call and migrates the state afterwards.
+    /// Can only be called by the owner.
+    pub fn upgrade(&self) -> Promise {
+        self.assert_owner();
+        let code = env::input().expect("The code to deploy is missing.");
+        Promise::new(env::current_account_id())
+            .deploy_contract(code)
+            .function_call(
+                "migrate".to_string(),
+                Vec::new(),
+                0,
+                env::prepaid_gas() - env::used_gas() - GAS_FOR_UPGRADE,
+            )
+    }
+
+    /// Migrates the next `limit` deeds of the first version. The deeds are added to the indexes
+    /// of their author and creditors and stored with the tag of `VersionedDeed`. Deeds cannot be
+    /// changed until they are migrated. Returns the number of deeds that are left. Can only be called by the owner.
+    pub fn migrate_deeds(&mut self, limit: Option<u64>) -> u64 {
+        self.assert_owner();
+        let limit = limit.unwrap_or(u64::MAX);
+        require!(limit != 0, "Cannot provide limit of 0.");
+        let end_id = self.deed_backfill.end.min(self.deed_backfill.next.saturating_add(limit));
+        for id in self.deed_backfill.next..end_id {
+            let deed = self.internal_get_deed(id);
+            deed_index::index_authored_deed(&mut self.authored_deeds, &deed.author, id);
+            for creditor in deed.creditors.iter() {
+                deed_index::index_credited_deed(&mut self.credited_deeds, &creditor, id);
+            }
+            // `replace` would read the untagged deed as `VersionedDeed`.
+            self.deeds.replace_raw(id, &VersionedDeed::from(deed).try_to_vec().unwrap());
+        }
+        self.deed_backfill.next = end_id;
+        self.deed_backfill.end - end_id
+    }
+
+    /// Retracts the deeds `from_index..from_index + limit` that earlier versions posted for every
+    /// donation to all users instead of recording the donation. Returns the index of the first
+    /// deed that was not checked. Can only be called by the owner.
+    pub fn retract_donation_deeds(&mut self, from_index: Option<u64>, limit: Option<u64>) -> u64 {
+        self.assert_owner();
+        let start_index = from_index.unwrap_or_default();
+        let end_index = self.deeds.len().min(start_index.saturating_add(limit.unwrap_or(u64::MAX)));
+        for id in start_index..end_index {
+            let deed = self.internal_get_deed(id);
+            if deed.status != DeedStatus::Retracted && deed.title == DONATION_DEED_TITLE && deed.proof == DONATION_DEED_PROOF {
+                self.internal_retract_deed(deed);
+            }
+        }
+        end_index
+    }
+
+    /// Moves the creditors of the deeds `from_index..from_index + limit` to the storage prefixes
+    /// of `StorageKey::DeedCreditors`. Deeds posted by earlier versions of the contract used the
+    /// decimal id as prefix, which made the prefixes of e.g. deed 1 and deed 10 overlap. Already
+    /// migrated deeds are rewritten unchanged, so the method can be called again safely. Returns
+    /// the index of the first deed that was not migrated. The deeds have to be migrated by
+    /// `migrate_deeds` first. Can only be called by the owner.
+    pub fn migrate_deed_creditors(&mut self, from_index: Option<u64>, limit: Option<u64>) -> u64 {
+        self.assert_owner();
+        let start_index = from_index.unwrap_or_default();
+        let end_index = self.deeds.len().min(start_index.saturating_add(limit.unwrap_or(u64::MAX)));
+        for id in start_index..end_index {
+            let mut deed = self.internal_get_deed(id);
+            let creditors = deed.creditors.to_vec();
+            deed.creditors.clear();
+            deed.creditors = UnorderedSet::new(StorageKey::DeedCreditors { deed_id: id });
+            deed.creditors.extend(creditors);
+            self.internal_save_deed(deed);
+        }
+        end_index
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_sdk::test_utils::accounts;
+    use near_sdk::testing_env;
+
+    use crate::tests::{call, get_context, TOTAL_SUPPLY};
+    use super::*;
+
+    /// Creates a deed the way the first version of the contract did.
+    fn legacy_deed(id: u64, creditors: &[AccountId]) -> SocialDeedV1 {
+        let mut deed = SocialDeedV1 {
+            id,
+            author: accounts(1),
+            title: "title".into(),
+            description: "description".into(),
+            proof: "proof".into(),
+            creditors: UnorderedSet::new(id.to_string().as_bytes())
+        };
+        deed.creditors.extend(creditors.iter().cloned());
+        deed
+    }
+
+    /// Writes the state of the first version with twelve deeds. The deed `id` has `id % 3`
+    /// creditors and its author got one DEED from the owner for every credit.
+    fn write_v1_state() {
+        let mut token = FungibleToken::new(StorageKey::Token);
+        token.internal_register_account(&accounts(2));
+        token.internal_register_account(&accounts(1));
+        token.internal_deposit(&accounts(2), TOTAL_SUPPLY);
+        let mut deeds = Vector::new(b"d".to_vec());
+        for id in 0..12 {
+            let creditors: Vec<AccountId> = (0..(id % 3)).map(|i| accounts(i as usize + 3)).collect();
+            if !creditors.is_empty() {
+                token.internal_transfer(&accounts(2), &accounts(1), creditors.len() as Balance, None);
+            }
+            deeds.push(&legacy_deed(id, &creditors));
+        }
+        let mut donatable_accounts = UnorderedSet::new(StorageKey::DonatableAccounts);
+        donatable_accounts.insert(&accounts(1));
+        env::state_write(&ContractV1 {
+            token,
+            metadata: LazyOption::new(StorageKey::Metadata, None),
+            deeds,
+            owner: accounts(2),
+            donatable_accounts
+        });
+    }
+
+    #[test]
+    fn test_migrate_v1_keeps_deeds_and_balances() {
+        let mut context = get_context(accounts(0));
+        testing_env!(context.build());
+        write_v1_state();
+
+        let mut contract = Contract::migrate();
+
+        assert_eq!(contract.deeds.len(), 12);
+        for id in 0..12 {
+            let deed = contract.internal_get_deed(id);
+            assert_eq!(deed.id, id);
+            assert_eq!(deed.author, accounts(1));
+            assert_eq!(deed.title, "title");
+            assert_eq!(deed.creditors.len(), id % 3);
+        }
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, 12);
+        assert_eq!(contract.ft_balance_of(accounts(2)).0, TOTAL_SUPPLY - 12);
+        assert_eq!(contract.ft_total_supply().0, TOTAL_SUPPLY);
+        assert_eq!(contract.owner_supply, TOTAL_SUPPLY - 12);
+        assert!(contract.deeds_by_author(accounts(1), None, None, None).is_empty());
+
+        testing_env!(context.predecessor_account_id(accounts(2)).build());
+        assert_eq!(contract.migrate_deeds(Some(5)), 7);
+        assert_eq!(contract.deeds_by_author(accounts(1), None, None, None).len(), 5);
+        assert_eq!(contract.migrate_deeds(None), 0);
+        assert_eq!(contract.migrate_deeds(None), 0);
+        for id in 0..12 {
+            assert_eq!(contract.internal_get_deed(id).creditors.len(), id % 3);
+        }
+        assert_eq!(contract.deeds_by_author(accounts(1), None, None, None).len(), 12);
+        assert_eq!(contract.deeds_credited_by(accounts(3), None, None).len(), 8);
+        assert_eq!(contract.deeds_credited_by(accounts(4), None, None).len(), 4);
+    }
+
+    #[test]
+    fn test_migrated_deeds_can_be_credited() {
+        let mut context = get_context(accounts(0));
+        testing_env!(context.build());
+        write_v1_state();
+        let mut contract = Contract::migrate();
+        testing_env!(context.predecessor_account_id(accounts(2)).build());
+        contract.migrate_deeds(None);
+
+        call(&mut context, accounts(5), crate::tests::SAFE_STORAGE_COST);
+        contract.credit(1);
+
+        let deed = contract.internal_get_deed(1);
+        assert_eq!(deed.creditors.len(), 2);
+        assert!(deed.creditors.contains(&accounts(3)));
+        assert!(deed.creditors.contains(&accounts(5)));
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, 13);
+    }
+
+    #[test]
+    #[should_panic(expected = "The deed 1 was not migrated yet.")]
+    fn test_deeds_cannot_change_before_they_are_migrated() {
+        let mut context = get_context(accounts(0));
+        testing_env!(context.build());
+        write_v1_state();
+        let mut contract = Contract::migrate();
+        testing_env!(context.predecessor_account_id(accounts(2)).build());
+        contract.migrate_deeds(Some(1));
+
+        call(&mut context, accounts(5), crate::tests::SAFE_STORAGE_COST);
+        contract.credit(1);
+    }
+
+    #[test]
+    fn test_retract_donation_deeds() {
+        let mut context = get_context(accounts(2));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into());
+        call(&mut context, accounts(1), crate::tests::SAFE_STORAGE_COST);
+        contract.storage_deposit(None, None);
+        contract.add_deed(accounts(1), "title".into(), "description".into(), DONATION_DEED_PROOF.into(), None, None);
+        contract.add_deed(accounts(1), DONATION_DEED_TITLE.into(), "bob donated 1 NEAR to all users.".into(), DONATION_DEED_PROOF.into(), None, None);
+        testing_env!(context.predecessor_account_id(accounts(3)).build());
+        contract.credit(1);
+
+        testing_env!(context.predecessor_account_id(accounts(2)).build());
+        assert_eq!(contract.retract_donation_deeds(None, Some(1)), 1);
+        assert_eq!(contract.retract_donation_deeds(Some(1), None), 2);
+        assert_eq!(contract.internal_get_deed(0).status, DeedStatus::Active);
+        assert_eq!(contract.internal_get_deed(1).status, DeedStatus::Retracted);
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, 0);
+        // Retracted deeds are not checked again.
+        assert_eq!(contract.retract_donation_deeds(None, None), 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the owner can call this method.")]
+    fn test_only_owner_upgrades() {
+        let mut context = get_context(accounts(2));
+        testing_env!(context.build());
+        let contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into());
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        contract.upgrade();
+    }
+
+    #[test]
+    fn test_migrate_deed_creditors_keeps_credits() {
+        let context = get_context(accounts(2));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into());
+        for id in 0..12 {
+            let creditors: Vec<AccountId> = (0..(id % 3)).map(|i| accounts(i as usize + 3)).collect();
+            contract.deeds.push(&VersionedDeed::V1(legacy_deed(id, &creditors)));
+        }
+
+        assert_eq!(contract.migrate_deed_creditors(None, Some(5)), 5);
+        assert_eq!(contract.migrate_deed_creditors(Some(5), None), 12);
+        // Migrating twice does not change anything.
+        assert_eq!(contract.migrate_deed_creditors(None, None), 12);
+
+        let legacy_keys = [b"1".as_ref(), b"10".as_ref(), b"11".as_ref()];
+        for id in 0..12 {
+            let deed = contract.internal_get_deed(id);
+            assert_eq!(deed.creditors.len(), id % 3);
+            for i in 0..(id % 3) {
+                assert!(deed.creditors.contains(&accounts(i as usize + 3)));
+            }
+        }
+        for key in legacy_keys {
+            let set: UnorderedSet<AccountId> = UnorderedSet::new(key);
+            assert!(!set.contains(&accounts(3)));
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the owner can call this method.")]
+    fn test_only_owner_migrates_deed_creditors() {
+        let mut context = get_context(accounts(2));
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into());
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        contract.migrate_deed_creditors(None, None);
+    }
+}
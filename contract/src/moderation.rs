@@ -0,0 +1,224 @@
+//! Reports of fraudulent deeds.
+//!
+//! Anybody can report an active deed. Accounts with the moderator role review the reports of a deed and either dismiss
+//! them or reject the deed. A rejected deed cannot be credited anymore, it is removed from the
+//! listings of its category and tags and the DEED it earned is slashed from its author.
+
+use crate::*;
+use crate::access_control::Role;
+
+/// Maximum length of the reason of a report in bytes.
+pub const MAX_REASON_LENGTH: usize = 280;
+/// Maximum number of open reports of a deed.
+pub const MAX_REPORTS: usize = 10;
+
+#[derive(BorshDeserialize, BorshSerialize, Deserialize, Serialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Report {
+    reporter: AccountId,
+    reason: String
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Reports the deed `id` as fraudulent. Every account can report a deed once and a deed has
+    /// at most `MAX_REPORTS` open reports.
+    #[payable]
+    pub fn report_deed(
+        &mut self,
+        id: u64,
+        reason: String
+    )
+    {
+        let initial_storage_usage = env::storage_usage();
+
+        let deed = self.internal_get_deed(id);
+        deed.assert_active();
+        let reporter = env::predecessor_account_id();
+        assert_ne!(reporter, deed.author, "You cannot report yourself.");
+        require!(!reason.is_empty() && reason.len() <= MAX_REASON_LENGTH, format!("The reason must have between 1 and {} bytes.", MAX_REASON_LENGTH));
+        let mut reports = self.reports.get(&id).unwrap_or_default();
+        assert!(reports.iter().all(|report| report.reporter != reporter), "{} already reported the deed of {}.", reporter, deed.author);
+        assert!(reports.len() < MAX_REPORTS, "The deed already has {} open reports.", MAX_REPORTS);
+        reports.push(Report { reporter: reporter.clone(), reason });
+        self.reports.insert(&id, &reports);
+        events::DeedReported { deed_id: id, author: &deed.author, reporter: &reporter }.emit();
+
+        refund_deposit(env::storage_usage() - initial_storage_usage);
+    }
+
+    /// Resolves the reports of the deed `id`. If `reject` is set the deed is rejected and the
+    /// DEED it earned is burned from its author, otherwise the reports are dismissed. Can only be
+    /// called by moderators.
+    pub fn resolve_reports(
+        &mut self,
+        id: u64,
+        reject: bool
+    )
+    {
+        self.assert_role(Role::Moderator);
+        let mut deed = self.internal_get_deed(id);
+        require!(self.reports.remove(&id).is_some(), "The deed has no open reports.");
+        let moderator = env::predecessor_account_id();
+        if !reject {
+            events::ReportsDismissed { deed_id: id, moderator: &moderator }.emit();
+            return;
+        }
+
+        deed.assert_active();
+        self.internal_unindex_deed(&deed);
+        deed.status = DeedStatus::Rejected;
+        deed.updated_at = env::block_timestamp();
+        let author = deed.author.clone();
+        let earned = deed.earned_reputation();
+        self.internal_save_deed(deed);
+        let memo = format!("Social deed {} of {} rejected by {}", id, author, moderator);
+        let slashed = self.internal_burn_reputation(&author, earned, &memo);
+        events::DeedRejected { deed_id: id, author: &author, moderator: &moderator, slashed: &slashed.into() }.emit();
+    }
+
+    /// Returns the open reports of the deed `id`.
+    pub fn get_reports(&self, id: u64) -> Vec<Report> {
+        self.reports.get(&id).unwrap_or_default()
+    }
+
+    /// Returns the ids of the deeds with open reports together with the number of reports.
+    pub fn reported_deeds(&self, from_index: Option<u64>, limit: Option<u64>) -> Vec<(u64, u64)> {
+        let start_index = from_index.unwrap_or_default();
+        let limit = limit.unwrap_or(u64::MAX);
+        require!(limit != 0, "Cannot provide limit of 0.");
+        let ids = self.reports.keys_as_vector();
+        let values = self.reports.values_as_vector();
+        (start_index..ids.len())
+            .take(limit as usize)
+            .map(|index| (ids.get(index).unwrap(), values.get(index).unwrap().len() as u64))
+            .collect()
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::testing_env;
+
+    use crate::tests::{call, get_context, SAFE_STORAGE_COST, TOTAL_SUPPLY};
+    use super::*;
+
+    /// Sets up a contract with a deed of accounts(1) credited by accounts(3), reported by
+    /// accounts(4) and accounts(5) as moderator.
+    fn setup(context: &mut VMContextBuilder) -> Contract {
+        testing_env!(context.attached_deposit(SAFE_STORAGE_COST).build());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into());
+        contract.grant_role(Role::Moderator, accounts(5));
+        call(context, accounts(1), SAFE_STORAGE_COST);
+        contract.storage_deposit(None, None);
+        contract.add_deed(accounts(1), "title".into(), "description".into(), "proof".into(), None, None);
+
+        call(context, accounts(3), SAFE_STORAGE_COST);
+        contract.credit(0);
+
+        call(context, accounts(4), SAFE_STORAGE_COST);
+        contract.report_deed(0, "The proof is fake.".into());
+        contract
+    }
+
+    #[test]
+    fn test_report_deed() {
+        let mut context = get_context(accounts(2));
+        let contract = setup(&mut context);
+
+        let reports = contract.get_reports(0);
+        assert_eq!(reports.len(), 1);
+        assert_eq!(reports[0].reporter, accounts(4));
+        assert_eq!(reports[0].reason, "The proof is fake.");
+        assert_eq!(contract.reported_deeds(None, None), vec![(0, 1)]);
+    }
+
+    #[test]
+    #[should_panic(expected = "eugene already reported the deed of bob.")]
+    fn test_cannot_report_twice() {
+        let mut context = get_context(accounts(2));
+        let mut contract = setup(&mut context);
+        contract.report_deed(0, "Still fake.".into());
+    }
+
+    #[test]
+    fn test_rejecting_slashes_reputation() {
+        let mut context = get_context(accounts(2));
+        let mut contract = setup(&mut context);
+
+        testing_env!(context.predecessor_account_id(accounts(5)).attached_deposit(0).build());
+        contract.resolve_reports(0, true);
+
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, 0);
+        assert_eq!(contract.internal_get_deed(0).status, DeedStatus::Rejected);
+        assert!(contract.reported_deeds(None, None).is_empty());
+        let deeds = contract.social_deeds(accounts(3), None, None, None, None, None).deeds;
+        assert_eq!(deeds[0].status, DeedStatus::Rejected);
+    }
+
+    #[test]
+    fn test_rejected_deed_leaves_category_and_tags() {
+        let mut context = get_context(accounts(2));
+        let mut contract = setup(&mut context);
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .predecessor_account_id(accounts(2))
+            .build());
+        contract.add_category("care".into());
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .predecessor_account_id(accounts(1))
+            .build());
+        contract.add_deed(accounts(1), "title".into(), "description".into(), "proof".into(), Some("care".into()), Some(vec!["help".into()]));
+        testing_env!(context
+            .storage_usage(env::storage_usage())
+            .predecessor_account_id(accounts(4))
+            .build());
+        contract.report_deed(1, "The proof is fake.".into());
+
+        testing_env!(context.predecessor_account_id(accounts(5)).attached_deposit(0).build());
+        contract.resolve_reports(1, true);
+
+        assert!(contract.deeds_by_category("care".into(), None, None, None).is_empty());
+        assert!(contract.deeds_by_tag("help".into(), None, None, None).is_empty());
+        testing_env!(context.predecessor_account_id(accounts(2)).build());
+        contract.remove_category("care".into());
+        assert!(contract.get_categories().is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "The deed already has 10 open reports.")]
+    fn test_reports_are_limited() {
+        let mut context = get_context(accounts(2));
+        let mut contract = setup(&mut context);
+        for index in 1..=MAX_REPORTS {
+            testing_env!(context
+                .storage_usage(env::storage_usage())
+                .predecessor_account_id(format!("reporter{}.near", index).parse().unwrap())
+                .build());
+            contract.report_deed(0, "The proof is fake.".into());
+        }
+    }
+
+    #[test]
+    fn test_dismissing_keeps_deed() {
+        let mut context = get_context(accounts(2));
+        let mut contract = setup(&mut context);
+
+        testing_env!(context.predecessor_account_id(accounts(5)).attached_deposit(0).build());
+        contract.resolve_reports(0, false);
+
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, 1);
+        assert_eq!(contract.internal_get_deed(0).status, DeedStatus::Active);
+        assert!(contract.get_reports(0).is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "Only accounts with the moderator role can call this method.")]
+    fn test_only_moderators_resolve_reports() {
+        let mut context = get_context(accounts(2));
+        let mut contract = setup(&mut context);
+        contract.resolve_reports(0, true);
+    }
+}
@@ -0,0 +1,154 @@
+//! Emergency pause switch.
+//!
+//! Admins can pause single features of the contract. Paused methods panic until the feature is
+//! unpaused again. Views and the withdrawal of storage deposits are never paused.
+
+use crate::*;
+use crate::access_control::Role;
+
+#[derive(BorshDeserialize, BorshSerialize, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+#[serde(rename_all = "snake_case")]
+pub enum Feature {
+    /// Posting, updating and retracting deeds.
+    Deeds,
+    /// Crediting deeds and withdrawing credits.
+    Credits,
+    /// Donations, their claims and the escrow of bounties.
+    Donations,
+    /// Storage deposits.
+    Storage,
+}
+
+impl Feature {
+    fn name(&self) -> &'static str {
+        match self {
+            Feature::Deeds => "deeds",
+            Feature::Credits => "credits",
+            Feature::Donations => "donations",
+            Feature::Storage => "storage",
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PauseStatus {
+    deeds: bool,
+    credits: bool,
+    donations: bool,
+    storage: bool
+}
+
+impl Contract {
+    pub(crate) fn is_paused(&self, feature: Feature) -> bool {
+        self.paused_features.contains(&feature)
+    }
+
+    /// Panics if `feature` is paused.
+    pub(crate) fn assert_not_paused(&self, feature: Feature) {
+        assert!(!self.is_paused(feature), "The feature {} is paused.", feature.name());
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Pauses `features`. Can only be called by admins.
+    pub fn pause(&mut self, features: Vec<Feature>) {
+        self.assert_role(Role::Admin);
+        for feature in features {
+            if !self.is_paused(feature) {
+                self.paused_features.push(feature);
+                events::FeaturePaused { feature, by: &env::predecessor_account_id() }.emit();
+            }
+        }
+    }
+
+    /// Unpauses `features`. Can only be called by admins.
+    pub fn unpause(&mut self, features: Vec<Feature>) {
+        self.assert_role(Role::Admin);
+        for feature in features {
+            if self.is_paused(feature) {
+                self.paused_features.retain(|paused| *paused != feature);
+                events::FeatureUnpaused { feature, by: &env::predecessor_account_id() }.emit();
+            }
+        }
+    }
+
+    pub fn get_pause_status(&self) -> PauseStatus {
+        PauseStatus {
+            deeds: self.is_paused(Feature::Deeds),
+            credits: self.is_paused(Feature::Credits),
+            donations: self.is_paused(Feature::Donations),
+            storage: self.is_paused(Feature::Storage)
+        }
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::testing_env;
+
+    use crate::tests::{call, get_context, SAFE_STORAGE_COST, TOTAL_SUPPLY};
+    use super::*;
+
+    /// Sets up a contract with a deed of accounts(1) and pauses `features`.
+    fn setup(context: &mut VMContextBuilder, features: Vec<Feature>) -> Contract {
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into());
+        call(context, accounts(1), SAFE_STORAGE_COST);
+        contract.storage_deposit(None, None);
+        contract.add_deed(accounts(1), "title".into(), "description".into(), "proof".into(), None, None);
+
+        testing_env!(context.predecessor_account_id(accounts(2)).build());
+        contract.pause(features);
+        call(context, accounts(3), SAFE_STORAGE_COST);
+        contract
+    }
+
+    #[test]
+    fn test_pause_status() {
+        let mut context = get_context(accounts(2));
+        let mut contract = setup(&mut context, vec![Feature::Credits, Feature::Storage]);
+        let status = contract.get_pause_status();
+        assert!(!status.deeds && status.credits && !status.donations && status.storage);
+
+        testing_env!(context.predecessor_account_id(accounts(2)).build());
+        contract.unpause(vec![Feature::Credits]);
+        let status = contract.get_pause_status();
+        assert!(!status.credits && status.storage);
+    }
+
+    #[test]
+    #[should_panic(expected = "The feature credits is paused.")]
+    fn test_cannot_credit_while_paused() {
+        let mut context = get_context(accounts(2));
+        let mut contract = setup(&mut context, vec![Feature::Credits]);
+        contract.credit(0);
+    }
+
+    #[test]
+    #[should_panic(expected = "The feature storage is paused.")]
+    fn test_cannot_deposit_storage_while_paused() {
+        let mut context = get_context(accounts(2));
+        let mut contract = setup(&mut context, vec![Feature::Storage]);
+        contract.storage_deposit(None, None);
+    }
+
+    #[test]
+    fn test_other_features_work_while_paused() {
+        let mut context = get_context(accounts(2));
+        let mut contract = setup(&mut context, vec![Feature::Deeds, Feature::Donations]);
+        contract.credit(0);
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "Only accounts with the admin role can call this method.")]
+    fn test_only_admins_pause() {
+        let mut context = get_context(accounts(2));
+        let mut contract = setup(&mut context, vec![]);
+        contract.pause(vec![Feature::Donations]);
+    }
+}
@@ -0,0 +1,159 @@
+//! Rate limits of deeds and credits.
+//!
+//! Admins can limit how many deeds and credits every account can post per epoch. Epochs are
+//! fixed windows of block time, so the quota of all accounts resets at the same time. Usage is
+//! only recorded while a limit is set.
+
+use crate::*;
+use crate::access_control::Role;
+use near_sdk::json_types::U64;
+
+const DAY: u64 = 86_400_000_000_000;
+
+#[derive(BorshDeserialize, BorshSerialize, Deserialize, Serialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct RateLimits {
+    /// Length of an epoch in nanoseconds.
+    pub epoch_length: U64,
+    /// Deeds an account can post per epoch, `None` for no limit.
+    pub max_deeds: Option<u32>,
+    /// Credits an account can give per epoch, `None` for no limit.
+    pub max_credits: Option<u32>
+}
+
+impl Default for RateLimits {
+    fn default() -> Self {
+        Self { epoch_length: DAY.into(), max_deeds: None, max_credits: None }
+    }
+}
+
+/// What an account did in its last active epoch.
+#[derive(BorshDeserialize, BorshSerialize, Default)]
+pub struct AccountUsage {
+    epoch: u64,
+    deeds: u32,
+    credits: u32
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct RemainingQuota {
+    /// Deeds the account can still post in this epoch, `None` if unlimited.
+    deeds: Option<u32>,
+    /// Credits the account can still give in this epoch, `None` if unlimited.
+    credits: Option<u32>,
+    /// Block timestamp at which the next epoch starts.
+    resets_at: U64
+}
+
+impl Contract {
+    fn current_epoch(&self) -> u64 {
+        env::block_timestamp() / self.rate_limits.epoch_length.0
+    }
+
+    /// Usage of `account_id` in the current epoch.
+    fn current_usage(&self, account_id: &AccountId) -> AccountUsage {
+        let epoch = self.current_epoch();
+        match self.rate_limit_usage.get(account_id) {
+            Some(usage) if usage.epoch == epoch => usage,
+            _ => AccountUsage { epoch, ..Default::default() },
+        }
+    }
+
+    /// Counts a deed of `account_id` against its quota. Panics if the quota is used up.
+    pub(crate) fn internal_use_deed_quota(&mut self, account_id: &AccountId) {
+        if let Some(max_deeds) = self.rate_limits.max_deeds {
+            let mut usage = self.current_usage(account_id);
+            assert!(usage.deeds < max_deeds, "{} reached the limit of {} deeds per epoch.", account_id, max_deeds);
+            usage.deeds += 1;
+            self.rate_limit_usage.insert(account_id, &usage);
+        }
+    }
+
+    /// Counts a credit of `account_id` against its quota. Panics if the quota is used up.
+    pub(crate) fn internal_use_credit_quota(&mut self, account_id: &AccountId) {
+        if let Some(max_credits) = self.rate_limits.max_credits {
+            let mut usage = self.current_usage(account_id);
+            assert!(usage.credits < max_credits, "{} reached the limit of {} credits per epoch.", account_id, max_credits);
+            usage.credits += 1;
+            self.rate_limit_usage.insert(account_id, &usage);
+        }
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Sets the rate limits of all accounts. Can only be called by admins.
+    pub fn set_rate_limits(&mut self, limits: RateLimits) {
+        self.assert_role(Role::Admin);
+        require!(limits.epoch_length.0 > 0, "The epoch length must be positive.");
+        self.rate_limits = limits;
+    }
+
+    pub fn get_rate_limits(&self) -> RateLimits {
+        self.rate_limits.clone()
+    }
+
+    /// Returns how many deeds and credits `account_id` can still post in the current epoch.
+    pub fn remaining_quota(&self, account_id: AccountId) -> RemainingQuota {
+        let usage = self.current_usage(&account_id);
+        RemainingQuota {
+            deeds: self.rate_limits.max_deeds.map(|max_deeds| max_deeds.saturating_sub(usage.deeds)),
+            credits: self.rate_limits.max_credits.map(|max_credits| max_credits.saturating_sub(usage.credits)),
+            resets_at: ((usage.epoch + 1) * self.rate_limits.epoch_length.0).into()
+        }
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::testing_env;
+
+    use crate::tests::{call, get_context, SAFE_STORAGE_COST, TOTAL_SUPPLY};
+    use super::*;
+
+    /// Sets up a contract limited to one deed and two credits per day.
+    fn setup(context: &mut VMContextBuilder) -> Contract {
+        testing_env!(context.block_timestamp(0).build());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into());
+        contract.set_rate_limits(RateLimits { epoch_length: DAY.into(), max_deeds: Some(1), max_credits: Some(2) });
+        contract
+    }
+
+    #[test]
+    fn test_remaining_quota() {
+        let mut context = get_context(accounts(2));
+        let mut contract = setup(&mut context);
+        call(context.block_timestamp(10), accounts(1), SAFE_STORAGE_COST);
+        contract.storage_deposit(None, None);
+        contract.add_deed(accounts(1), "title".into(), "description".into(), "proof".into(), None, None);
+        call(context.block_timestamp(10), accounts(2), SAFE_STORAGE_COST);
+        contract.add_deed(accounts(2), "title".into(), "description".into(), "proof".into(), None, None);
+        contract.credit(0);
+
+        call(context.block_timestamp(20), accounts(1), SAFE_STORAGE_COST);
+        contract.credit(1);
+        let quota = contract.remaining_quota(accounts(1));
+        assert_eq!(quota.deeds, Some(0));
+        assert_eq!(quota.credits, Some(1));
+        assert_eq!(quota.resets_at.0, DAY);
+
+        call(context.block_timestamp(DAY), accounts(1), SAFE_STORAGE_COST);
+        let quota = contract.remaining_quota(accounts(1));
+        assert_eq!(quota.deeds, Some(1));
+        assert_eq!(quota.credits, Some(2));
+        assert_eq!(quota.resets_at.0, 2 * DAY);
+        contract.add_deed(accounts(1), "title".into(), "description".into(), "proof".into(), None, None);
+    }
+
+    #[test]
+    #[should_panic(expected = "bob reached the limit of 1 deeds per epoch.")]
+    fn test_deeds_are_limited() {
+        let mut context = get_context(accounts(2));
+        let mut contract = setup(&mut context);
+        call(context.block_timestamp(10), accounts(1), SAFE_STORAGE_COST);
+        contract.add_deed(accounts(1), "title".into(), "description".into(), "proof".into(), None, None);
+        contract.add_deed(accounts(1), "title".into(), "description".into(), "proof".into(), None, None);
+    }
+}
@@ -0,0 +1,252 @@
+//! Minting of the DEED reputation token.
+//!
+//! DEED is minted whenever a deed is credited, so the supply grows with the reputation of the
+//! authors. The owner can limit the total supply with an optional cap.
+//!
+//! By default every credit is worth one DEED. The owner can weight credits by the DEED balance of
+//! the creditor instead. The weight of every credit is recorded with the deed, so withdrawing the
+//! credit or retracting the deed burns exactly what the credit minted, even if the weighting or
+//! the balance of the creditor changed in the meantime.
+
+use crate::*;
+use crate::access_control::Role;
+use near_contract_standards::fungible_token::events::{FtBurn, FtMint};
+
+/// How much DEED a credit mints to the author of the deed.
+#[derive(BorshDeserialize, BorshSerialize, Deserialize, Serialize, Clone, PartialEq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum CreditWeighting {
+    /// Every credit is worth one DEED.
+    Flat,
+    /// A credit is worth `1 + floor(log2(balance + 1))` DEED, where `balance` is the DEED balance
+    /// of the creditor.
+    Logarithmic,
+    /// A credit is worth one DEED plus one DEED for every threshold the DEED balance of the
+    /// creditor reaches. The thresholds are strictly ascending.
+    Tiered { thresholds: Vec<U128> },
+}
+
+impl CreditWeighting {
+    /// The weight of a credit of an account with `balance` DEED.
+    pub fn weight(&self, balance: Balance) -> Balance {
+        match self {
+            CreditWeighting::Flat => 1,
+            CreditWeighting::Logarithmic => Balance::from(Balance::BITS - balance.saturating_add(1).leading_zeros()),
+            CreditWeighting::Tiered { thresholds } => {
+                1 + thresholds.iter().filter(|threshold| balance >= threshold.0).count() as Balance
+            }
+        }
+    }
+}
+
+impl Contract {
+    /// DEED a credit of `creditor_id` mints with the current weighting.
+    pub(crate) fn credit_weight(&self, creditor_id: &AccountId) -> Balance {
+        let balance = match &self.credit_weighting {
+            CreditWeighting::Flat => 0,
+            _ => self.token.ft_balance_of(creditor_id.clone()).into(),
+        };
+        self.credit_weighting.weight(balance)
+    }
+
+    /// Mints `amount` DEED to `account_id`. Panics if this exceeds the maximum supply.
+    pub(crate) fn internal_mint_reputation(&mut self, account_id: &AccountId, amount: Balance, memo: &str) {
+        if let Some(max_supply) = self.max_supply {
+            require!(self.token.total_supply + amount <= max_supply, "The maximum supply of DEED is reached.");
+        }
+        self.internal_settle_donations(account_id);
+        self.token.internal_deposit(account_id, amount);
+        FtMint {
+            owner_id: account_id,
+            amount: &amount.into(),
+            memo: Some(memo),
+        }
+        .emit();
+    }
+
+    /// Burns up to `amount` DEED of `account_id` and returns the amount actually burned.
+    pub(crate) fn internal_burn_reputation(&mut self, account_id: &AccountId, amount: Balance, memo: &str) -> Balance {
+        let amount = amount.min(self.token.ft_balance_of(account_id.clone()).into());
+        if amount == 0 {
+            return 0;
+        }
+        self.internal_settle_donations(account_id);
+        self.token.internal_withdraw(account_id, amount);
+        FtBurn {
+            owner_id: account_id,
+            amount: &amount.into(),
+            memo: Some(memo),
+        }
+        .emit();
+        amount
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Sets the maximum total supply of DEED. `None` removes the cap. Can only be called by
+    /// admins.
+    pub fn set_max_supply(&mut self, max_supply: Option<U128>) {
+        self.assert_role(Role::Admin);
+        let max_supply = max_supply.map(Balance::from);
+        if let Some(max_supply) = max_supply {
+            require!(max_supply >= self.token.total_supply, "The maximum supply cannot be below the current supply.");
+        }
+        self.max_supply = max_supply;
+    }
+
+    pub fn get_max_supply(&self) -> Option<U128> {
+        self.max_supply.map(U128::from)
+    }
+
+    /// Sets how much DEED future credits mint. Existing credits keep their weight. Can only be
+    /// called by the owner.
+    pub fn set_credit_weighting(&mut self, weighting: CreditWeighting) {
+        self.assert_owner();
+        if let CreditWeighting::Tiered { thresholds } = &weighting {
+            require!(thresholds.windows(2).all(|pair| pair[0].0 < pair[1].0), "The thresholds must be strictly ascending.");
+        }
+        self.credit_weighting = weighting;
+    }
+
+    pub fn get_credit_weighting(&self) -> CreditWeighting {
+        self.credit_weighting.clone()
+    }
+
+    /// Burns the supply that was minted to the owner at initialization. Contracts deployed before
+    /// DEED was minted on credit hold the reputation of all authors in the owner account, this
+    /// moves them to a supply that only contains the reputation actually earned. Reputation the
+    /// owner earned as author is kept.
+    pub fn burn_owner_supply(&mut self) -> U128 {
+        self.assert_owner();
+        let balance: Balance = self.token.ft_balance_of(self.owner.clone()).into();
+        let amount = self.owner_supply.min(balance);
+        require!(amount > 0, "The owner does not hold any supply.");
+        self.token.internal_withdraw(&self.owner, amount);
+        self.owner_supply = 0;
+        FtBurn {
+            owner_id: &self.owner,
+            amount: &amount.into(),
+            memo: Some("Pre-minted supply of the owner is burned"),
+        }
+        .emit();
+        amount.into()
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::testing_env;
+
+    use crate::tests::{call, get_context, SAFE_STORAGE_COST, TOTAL_SUPPLY};
+    use super::*;
+
+    /// Sets up a contract with a deed of accounts(1) and registers accounts(3) as creditor.
+    fn setup(context: &mut VMContextBuilder, total_supply: Balance) -> Contract {
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(2), total_supply.into());
+        call(context, accounts(1), SAFE_STORAGE_COST);
+        contract.storage_deposit(None, None);
+        contract.add_deed(accounts(1), "title".into(), "description".into(), "proof".into(), None, None);
+        contract.add_deed(accounts(1), "title".into(), "description".into(), "proof".into(), None, None);
+
+        call(context, accounts(3), SAFE_STORAGE_COST);
+        contract.storage_deposit(None, None);
+        contract
+    }
+
+    #[test]
+    fn test_credit_without_initial_supply() {
+        let mut context = get_context(accounts(2));
+        let mut contract = setup(&mut context, 0);
+        contract.credit(0);
+
+        assert_eq!(contract.ft_total_supply().0, 1);
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "The maximum supply of DEED is reached.")]
+    fn test_credit_respects_max_supply() {
+        let mut context = get_context(accounts(2));
+        let mut contract = setup(&mut context, 0);
+        testing_env!(context.predecessor_account_id(accounts(2)).build());
+        contract.set_max_supply(Some(1.into()));
+
+        call(&mut context, accounts(3), SAFE_STORAGE_COST);
+        contract.credit(0);
+        contract.credit(1);
+    }
+
+    #[test]
+    #[should_panic(expected = "Only accounts with the admin role can call this method.")]
+    fn test_only_admins_set_max_supply() {
+        let mut context = get_context(accounts(2));
+        let mut contract = setup(&mut context, 0);
+        contract.set_max_supply(Some(1.into()));
+    }
+
+    #[test]
+    fn test_weights() {
+        assert_eq!(CreditWeighting::Flat.weight(1000), 1);
+        let logarithmic = CreditWeighting::Logarithmic;
+        assert_eq!([0, 1, 2, 3, 7, 8].iter().map(|balance| logarithmic.weight(*balance)).collect::<Vec<_>>(), [1, 2, 2, 3, 4, 4]);
+        assert_eq!(logarithmic.weight(Balance::MAX), 128);
+        let tiered = CreditWeighting::Tiered { thresholds: vec![5.into(), 50.into()] };
+        assert_eq!([0, 5, 49, 50].iter().map(|balance| tiered.weight(*balance)).collect::<Vec<_>>(), [1, 2, 2, 3]);
+    }
+
+    #[test]
+    fn test_weighted_credit_is_reversed_with_recorded_weight() {
+        let mut context = get_context(accounts(2));
+        let mut contract = setup(&mut context, 0);
+        call(&mut context, accounts(2), SAFE_STORAGE_COST);
+        contract.set_credit_weighting(CreditWeighting::Tiered { thresholds: vec![1.into()] });
+        contract.add_deed(accounts(2), "title".into(), "description".into(), "proof".into(), None, None);
+
+        call(&mut context, accounts(1), SAFE_STORAGE_COST);
+        // Without DEED the credit of bob is worth 1.
+        contract.credit(2);
+        call(&mut context, accounts(3), SAFE_STORAGE_COST);
+        contract.credit(0);
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, 1);
+
+        // With one DEED the next credit of bob is worth 2.
+        call(&mut context, accounts(2), SAFE_STORAGE_COST);
+        contract.add_deed(accounts(2), "title".into(), "description".into(), "proof".into(), None, None);
+        call(&mut context, accounts(1), SAFE_STORAGE_COST);
+        contract.credit(3);
+        assert_eq!(contract.internal_get_deed(3).earned_reputation(), 2);
+
+        testing_env!(context.predecessor_account_id(accounts(2)).build());
+        contract.set_credit_weighting(CreditWeighting::Flat);
+        testing_env!(context.predecessor_account_id(accounts(1)).attached_deposit(0).build());
+        contract.uncredit(3);
+        assert_eq!(contract.internal_get_deed(3).earned_reputation(), 0);
+        assert_eq!(contract.ft_total_supply().0, 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "The thresholds must be strictly ascending.")]
+    fn test_tiers_must_be_ascending() {
+        let mut context = get_context(accounts(2));
+        let mut contract = setup(&mut context, 0);
+        testing_env!(context.predecessor_account_id(accounts(2)).build());
+        contract.set_credit_weighting(CreditWeighting::Tiered { thresholds: vec![5.into(), 5.into()] });
+    }
+
+    #[test]
+    fn test_burn_owner_supply() {
+        let mut context = get_context(accounts(2));
+        let mut contract = setup(&mut context, TOTAL_SUPPLY);
+        contract.credit(0);
+
+        testing_env!(context.predecessor_account_id(accounts(2)).build());
+        assert_eq!(contract.burn_owner_supply().0, TOTAL_SUPPLY);
+        assert_eq!(contract.ft_total_supply().0, 1);
+        assert_eq!(contract.ft_balance_of(accounts(2)).0, 0);
+        assert_eq!(contract.donatable_supply(), 1);
+    }
+}
@@ -0,0 +1,125 @@
+//! Chronological queries of deeds.
+//!
+//! Deeds are stored in the order they were posted, so their creation timestamps never decrease
+//! with the id. This allows to find the deeds of a time range with a binary search. Deeds posted
+//! before the contract recorded timestamps have the creation timestamp 0.
+
+use crate::*;
+
+impl Contract {
+    /// Returns the id of the first deed created at or after `timestamp`.
+    fn first_deed_at(&self, timestamp: u64) -> u64 {
+        let (mut low, mut high) = (0, self.deeds.len());
+        while low < high {
+            let middle = low + (high - low) / 2;
+            if self.internal_get_deed(middle).created_at < timestamp {
+                low = middle + 1;
+            } else {
+                high = middle;
+            }
+        }
+        low
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Returns up to `limit` deeds created in the block time range `[from, to)` in the order they
+    /// were posted, starting at the deed `from_index` if it is given. `next_cursor` is the
+    /// `from_index` of the next page and `None` after the last page. `is_creditor` refers to
+    /// `creditor_id` if it is given.
+    pub fn deeds_between(&self, from: U64, to: U64, from_index: Option<U64>, limit: Option<u64>,
+                         creditor_id: Option<AccountId>) -> DeedsPage {
+        let limit = limit.unwrap_or(u64::MAX);
+        require!(limit != 0, "Cannot provide limit of 0.");
+        let start_id = self.first_deed_at(from.0).max(from_index.map_or(0, |index| index.0));
+        let end_id = self.first_deed_at(to.0);
+        let page_end_id = end_id.min(start_id.saturating_add(limit));
+        let deeds = (start_id..page_end_id)
+            .map(|id| SerializableDeed::new(self.internal_get_deed(id), creditor_id.as_ref()))
+            .collect();
+        DeedsPage { deeds, next_cursor: (page_end_id < end_id).then(|| page_end_id.into()) }
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::testing_env;
+
+    use crate::tests::{call, get_context, SAFE_STORAGE_COST, TOTAL_SUPPLY};
+    use super::*;
+
+    /// Sets up a contract with deeds of accounts(1) posted at 10, 20, 20 and 30.
+    fn setup(context: &mut VMContextBuilder) -> Contract {
+        testing_env!(context.build());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into());
+        call(context.block_timestamp(0), accounts(1), SAFE_STORAGE_COST);
+        contract.storage_deposit(None, None);
+        for timestamp in [10, 20, 20, 30].iter() {
+            call(context.block_timestamp(*timestamp), accounts(1), SAFE_STORAGE_COST);
+            contract.add_deed(accounts(1), "title".into(), "description".into(), "proof".into(), None, None);
+        }
+        contract
+    }
+
+    fn ids(deeds: Vec<SerializableDeed>) -> Vec<u64> {
+        deeds.iter().map(|deed| deed.id).collect()
+    }
+
+    #[test]
+    fn test_deeds_between() {
+        let mut context = get_context(accounts(2));
+        let contract = setup(&mut context);
+
+        assert_eq!(ids(contract.deeds_between(20.into(), 30.into(), None, None, None).deeds), vec![1, 2]);
+        assert_eq!(ids(contract.deeds_between(0.into(), 100.into(), None, Some(3), None).deeds), vec![0, 1, 2]);
+        assert_eq!(ids(contract.deeds_between(11.into(), 20.into(), None, None, None).deeds), Vec::<u64>::new());
+        assert_eq!(ids(contract.deeds_between(31.into(), 40.into(), None, None, None).deeds), Vec::<u64>::new());
+    }
+
+    #[test]
+    fn test_page_deeds_of_one_timestamp() {
+        let mut context = get_context(accounts(2));
+        let contract = setup(&mut context);
+
+        let page = contract.deeds_between(20.into(), 21.into(), None, Some(1), None);
+        assert_eq!(ids(page.deeds), vec![1]);
+        assert_eq!(page.next_cursor, Some(2.into()));
+        let page = contract.deeds_between(20.into(), 21.into(), page.next_cursor, Some(1), None);
+        assert_eq!(ids(page.deeds), vec![2]);
+        assert_eq!(page.next_cursor, None);
+        // A cursor before the range starts at the range.
+        assert_eq!(ids(contract.deeds_between(20.into(), 30.into(), Some(0.into()), None, None).deeds), vec![1, 2]);
+    }
+
+    #[test]
+    fn test_newest_deeds_first() {
+        let mut context = get_context(accounts(2));
+        let contract = setup(&mut context);
+
+        let page = contract.social_deeds(accounts(3), None, Some(2), None, None, Some(true));
+        assert_eq!(ids(page.deeds), vec![3, 2]);
+        assert_eq!(page.next_cursor, Some(1.into()));
+        let page = contract.social_deeds(accounts(3), page.next_cursor, None, None, None, Some(true));
+        assert_eq!(ids(page.deeds), vec![1, 0]);
+        assert_eq!(page.next_cursor, None);
+    }
+
+    #[test]
+    fn test_timestamps_of_deed_and_credit() {
+        let mut context = get_context(accounts(2));
+        let mut contract = setup(&mut context);
+        call(context.block_timestamp(35), accounts(1), SAFE_STORAGE_COST);
+        contract.update_deed(3, Some("new title".into()), None, None, None, None);
+        call(context.block_timestamp(40), accounts(3), SAFE_STORAGE_COST);
+        contract.credit(3);
+
+        let deed = SerializableDeed::new(contract.internal_get_deed(3), Some(&accounts(3)));
+        assert_eq!(deed.created_at.0, 30);
+        assert_eq!(deed.updated_at.0, 35);
+        assert_eq!(deed.credited_at.map(|timestamp| timestamp.0), Some(40));
+        let deed = SerializableDeed::new(contract.internal_get_deed(3), Some(&accounts(4)));
+        assert!(deed.credited_at.is_none());
+    }
+}
@@ -0,0 +1,384 @@
+//! Donations of NEP-141 fungible tokens.
+//!
+//! Sponsors donate tokens with `ft_transfer_call` to this contract. Every token of the allowlist
+//! has its own reward-per-DEED index that works like the one of NEAR donations, see
+//! `donation.rs`. Accounts withdraw their accrued tokens with `claim_token_donations`, which
+//! transfers them with `ft_transfer`. If the transfer fails, e.g. because the account is not
+//! registered with the token contract, the tokens stay claimable.
+//!
+//! Settlements happen in calls that attach no deposit, e.g. `ft_transfer`. The storage of the
+//! donation accounts of a DEED holder, one for NEAR and one for every token that can be donated,
+//! is therefore paid with the storage deposit of its registration.
+
+use crate::*;
+use crate::donation::DonationAccount;
+use crate::pause::Feature;
+use near_contract_standards::fungible_token::core_impl::ext_fungible_token;
+use near_contract_standards::fungible_token::receiver::FungibleTokenReceiver;
+use near_sdk::{assert_one_yocto, ext_contract, is_promise_success, Gas, StorageUsage};
+
+/// Maximum number of tokens that can be donated. Every token is settled whenever a DEED balance
+/// changes, so the allowlist has to stay small.
+pub const MAX_DONATION_TOKENS: u64 = 5;
+
+const GAS_FOR_FT_TRANSFER: Gas = Gas(10_000_000_000_000);
+const GAS_FOR_RESOLVE_CLAIM: Gas = Gas(10_000_000_000_000);
+
+#[derive(BorshDeserialize, BorshSerialize, Default)]
+pub struct TokenPool {
+    /// Reward-per-DEED index in the smallest unit of the token.
+    reward_per_token: u128,
+    undistributed: Balance,
+    /// Whether new donations of the token are accepted.
+    accepting: bool
+}
+
+#[ext_contract(ext_self)]
+trait TokenDonationResolver {
+    fn on_token_donation_claimed(&mut self, token_id: AccountId, account_id: AccountId, amount: U128) -> U128;
+}
+
+impl Contract {
+    /// Returns the bytes the donation accounts of a single DEED holder take up at most. Measured
+    /// with the longest account ids, like the storage of a registration.
+    pub(crate) fn measure_donation_storage_usage(&mut self) -> StorageUsage {
+        let account_id: AccountId = "a".repeat(64).parse().unwrap();
+        let key = (account_id.clone(), account_id.clone());
+        let initial_storage_usage = env::storage_usage();
+        self.donation_accounts.insert(&account_id, &DonationAccount::default());
+        let near_storage_usage = env::storage_usage() - initial_storage_usage;
+        self.token_donation_accounts.insert(&key, &DonationAccount::default());
+        let token_storage_usage = env::storage_usage() - initial_storage_usage - near_storage_usage;
+        self.donation_accounts.remove(&account_id);
+        self.token_donation_accounts.remove(&key);
+        near_storage_usage + MAX_DONATION_TOKENS * token_storage_usage
+    }
+
+    fn pending_token_donations(&self, pool: &TokenPool, account_id: &AccountId, account: &DonationAccount) -> Balance {
+        let balance: Balance = self.token.ft_balance_of(account_id.clone()).into();
+        balance * (pool.reward_per_token - account.reward_per_token_paid)
+    }
+
+    /// Settles the donations of the token `token_id` accrued by `account_id`.
+    fn internal_settle_token_donation(&mut self, token_id: &AccountId, pool: &TokenPool, account_id: &AccountId) {
+        let key = (token_id.clone(), account_id.clone());
+        let mut account = self.token_donation_accounts.get(&key).unwrap_or_default();
+        account.claimable += self.pending_token_donations(pool, account_id, &account);
+        account.reward_per_token_paid = pool.reward_per_token;
+        self.token_donation_accounts.insert(&key, &account);
+    }
+
+    /// Settles the donations of all tokens accrued by `account_id`. Called together with the
+    /// settlement of NEAR donations.
+    pub(crate) fn internal_settle_token_donations(&mut self, account_id: &AccountId) {
+        for (token_id, pool) in self.donation_tokens.to_vec() {
+            self.internal_settle_token_donation(&token_id, &pool, account_id);
+        }
+    }
+
+    /// Starts the accrual of `account_id` at the current index of every token, see
+    /// `internal_skip_donations`.
+    pub(crate) fn internal_skip_token_donations(&mut self, account_id: &AccountId) {
+        for (token_id, pool) in self.donation_tokens.to_vec() {
+            let key = (token_id, account_id.clone());
+            let mut account = self.token_donation_accounts.get(&key).unwrap_or_default();
+            account.reward_per_token_paid = pool.reward_per_token;
+            self.token_donation_accounts.insert(&key, &account);
+        }
+    }
+}
+
+#[near_bindgen]
+impl FungibleTokenReceiver for Contract {
+    /// Distributes the transferred tokens to all DEED holders proportional to their DEED balance.
+    /// The sender is not part of the distribution of its own donation. Panics, and thereby
+    /// refunds the tokens, if the token is not accepted or nobody can receive the donation.
+    fn ft_on_transfer(
+        &mut self,
+        sender_id: AccountId,
+        amount: U128,
+        #[allow(unused_variables)]
+        msg: String,
+    ) -> PromiseOrValue<U128> {
+        self.assert_not_paused(Feature::Donations);
+        let token_id = env::predecessor_account_id();
+        let mut pool = self.donation_tokens.get(&token_id).unwrap_or_default();
+        assert!(pool.accepting, "Donations of {} are not accepted.", token_id);
+
+        // The supply of the owner is not part of the donatable supply anyway.
+        let donor_balance: Balance = if sender_id == self.owner { 0 } else { self.token.ft_balance_of(sender_id.clone()).into() };
+        let supply = self.donatable_supply() - donor_balance;
+        require!(supply > 0, "There is nobody with reputation to donate to.");
+        let donor_key = (token_id.clone(), sender_id.clone());
+        if donor_balance > 0 {
+            self.internal_settle_token_donation(&token_id, &pool, &sender_id);
+        }
+
+        let total = amount.0 + pool.undistributed;
+        let increment = total / supply;
+        pool.reward_per_token += increment;
+        pool.undistributed = total - increment * supply;
+        if let Some(mut account) = self.token_donation_accounts.get(&donor_key) {
+            // Skip the share of the own donation.
+            account.reward_per_token_paid = pool.reward_per_token;
+            self.token_donation_accounts.insert(&donor_key, &account);
+        }
+        self.donation_tokens.insert(&token_id, &pool);
+        self.internal_add_prepaid_donation(&sender_id, amount.0, Some(token_id.clone()));
+        events::TokenDonationReceived { token_id: &token_id, donor: &sender_id, amount: &amount }.emit();
+        PromiseOrValue::Value(0.into())
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Accepts donations of the token `token_id`. Can only be called by the owner.
+    #[payable]
+    pub fn add_donation_token(&mut self, token_id: AccountId) {
+        let initial_storage_usage = env::storage_usage();
+
+        self.assert_owner();
+        let mut pool = match self.donation_tokens.get(&token_id) {
+            Some(pool) => pool,
+            None => {
+                require!(self.donation_tokens.len() < MAX_DONATION_TOKENS, format!("At most {} tokens can be donated.", MAX_DONATION_TOKENS));
+                TokenPool::default()
+            }
+        };
+        pool.accepting = true;
+        self.donation_tokens.insert(&token_id, &pool);
+
+        refund_deposit(env::storage_usage() - initial_storage_usage);
+    }
+
+    /// Stops accepting donations of the token `token_id`. Already donated tokens can still be
+    /// claimed. Can only be called by the owner.
+    pub fn remove_donation_token(&mut self, token_id: AccountId) {
+        self.assert_owner();
+        let mut pool = self.donation_tokens.get(&token_id).expect("The token is not accepted.");
+        pool.accepting = false;
+        self.donation_tokens.insert(&token_id, &pool);
+    }
+
+    /// Returns the tokens that can currently be donated.
+    pub fn get_donation_tokens(&self) -> Vec<AccountId> {
+        self.donation_tokens.iter()
+            .filter(|(_, pool)| pool.accepting)
+            .map(|(token_id, _)| token_id)
+            .collect()
+    }
+
+    /// Returns the amount of `token_id` that was left over by rounding and will be part of the
+    /// next donation of the token.
+    pub fn undistributed_token_donations(&self, token_id: AccountId) -> U128 {
+        self.donation_tokens.get(&token_id).map_or(0, |pool| pool.undistributed).into()
+    }
+
+    /// Returns the amount of `token_id` `account_id` can currently claim.
+    pub fn claimable_token_donations(&self, token_id: AccountId, account_id: AccountId) -> U128 {
+        let pool = match self.donation_tokens.get(&token_id) {
+            Some(pool) => pool,
+            None => return 0.into(),
+        };
+        let account = self.token_donation_accounts.get(&(token_id, account_id.clone())).unwrap_or_default();
+        if account_id == self.owner {
+            return account.claimable.into();
+        }
+        (account.claimable + self.pending_token_donations(&pool, &account_id, &account)).into()
+    }
+
+    /// Transfers all donations of `token_id` accrued by the caller to the caller. Requires an
+    /// attached deposit of exactly 1 yoctoNEAR.
+    #[payable]
+    pub fn claim_token_donations(&mut self, token_id: AccountId) -> Promise {
+        assert_one_yocto();
+        self.assert_not_paused(Feature::Donations);
+        let account_id = env::predecessor_account_id();
+        let amount: Balance = self.claimable_token_donations(token_id.clone(), account_id.clone()).into();
+        require!(amount > 0, "There are no donations to claim.");
+        let pool = self.donation_tokens.get(&token_id).unwrap();
+        if account_id != self.owner {
+            self.internal_settle_token_donation(&token_id, &pool, &account_id);
+        }
+        let key = (token_id.clone(), account_id.clone());
+        let mut account = self.token_donation_accounts.get(&key).unwrap();
+        account.claimable = 0;
+        self.token_donation_accounts.insert(&key, &account);
+
+        ext_fungible_token::ft_transfer(
+            account_id.clone(),
+            amount.into(),
+            Some("Donations to the authors of social deeds".to_string()),
+            token_id.clone(),
+            1,
+            GAS_FOR_FT_TRANSFER,
+        )
+        .then(ext_self::on_token_donation_claimed(
+            token_id,
+            account_id,
+            amount.into(),
+            env::current_account_id(),
+            0,
+            GAS_FOR_RESOLVE_CLAIM,
+        ))
+    }
+
+    /// Resolves a claim of token donations. If the transfer failed the amount is claimable again.
+    /// Returns the amount that was paid out.
+    #[private]
+    pub fn on_token_donation_claimed(&mut self, token_id: AccountId, account_id: AccountId, amount: U128) -> U128 {
+        if is_promise_success() {
+            events::TokenDonationPaid { token_id: &token_id, account_id: &account_id, amount: &amount }.emit();
+            return amount;
+        }
+        log!("The transfer of {} {} to {} failed, make sure the account is registered with the token.", amount.0, token_id, account_id);
+        let key = (token_id, account_id);
+        let mut account = self.token_donation_accounts.get(&key).unwrap_or_default();
+        account.claimable += amount.0;
+        self.token_donation_accounts.insert(&key, &account);
+        0.into()
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::{testing_env, PromiseResult, RuntimeFeesConfig, VMConfig};
+
+    use crate::tests::{call, get_context, SAFE_STORAGE_COST, TOTAL_SUPPLY};
+    use super::*;
+
+    fn usdc() -> AccountId {
+        "usdc.near".parse().unwrap()
+    }
+
+    /// Sets up a contract accepting usdc where accounts(1) holds two and accounts(3) one DEED.
+    fn setup(context: &mut VMContextBuilder) -> Contract {
+        testing_env!(context.attached_deposit(SAFE_STORAGE_COST).build());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into());
+        contract.add_donation_token(usdc());
+        for account_id in [accounts(1), accounts(3)].iter() {
+            call(context, account_id.clone(), SAFE_STORAGE_COST);
+            contract.storage_deposit(None, None);
+            contract.add_deed(account_id.clone(), "title".into(), "description".into(), "proof".into(), None, None);
+        }
+        contract.credit(0);
+        call(context, accounts(4), SAFE_STORAGE_COST);
+        contract.credit(0);
+        contract.credit(1);
+        contract
+    }
+
+    fn transfer(context: &mut VMContextBuilder, contract: &mut Contract, token_id: AccountId, sender_id: AccountId, amount: Balance) {
+        call(context, token_id, 0);
+        contract.ft_on_transfer(sender_id, amount.into(), String::new());
+    }
+
+    #[test]
+    fn test_token_donation_is_distributed() {
+        let mut context = get_context(accounts(2));
+        let mut contract = setup(&mut context);
+        transfer(&mut context, &mut contract, usdc(), accounts(5), 1000);
+
+        assert_eq!(contract.claimable_token_donations(usdc(), accounts(1)).0, 666);
+        assert_eq!(contract.claimable_token_donations(usdc(), accounts(3)).0, 333);
+        assert_eq!(contract.undistributed_token_donations(usdc()).0, 1);
+
+        // A credit settles the donations accrued so far before the balance changes.
+        testing_env!(context.predecessor_account_id(accounts(1)).attached_deposit(SAFE_STORAGE_COST).build());
+        contract.credit(1);
+        assert_eq!(contract.claimable_token_donations(usdc(), accounts(3)).0, 333);
+        transfer(&mut context, &mut contract, usdc(), accounts(5), 1000);
+        assert_eq!(contract.claimable_token_donations(usdc(), accounts(1)).0, 1166);
+        assert_eq!(contract.claimable_token_donations(usdc(), accounts(3)).0, 833);
+    }
+
+    #[test]
+    fn test_token_donation_is_recorded_with_record_storage() {
+        let mut context = get_context(accounts(2));
+        let mut contract = setup(&mut context);
+        transfer(&mut context, &mut contract, usdc(), accounts(5), 1000);
+        assert_eq!(contract.get_donations_count(), 0);
+
+        call(&mut context, accounts(5), SAFE_STORAGE_COST);
+        let record_storage = contract.deposit_record_storage().0;
+        transfer(&mut context, &mut contract, usdc(), accounts(5), 1000);
+        assert_eq!(contract.get_donations_count(), 1);
+        let cost = record_storage - contract.record_storage_of(accounts(5)).0;
+        assert_eq!(cost, contract.donation_record_cost(&accounts(5), Some(usdc())));
+        assert_eq!(contract.claimable_token_donations(usdc(), accounts(1)).0, 1332);
+    }
+
+    #[test]
+    #[should_panic(expected = "Donations of dai.near are not accepted.")]
+    fn test_only_accepted_tokens_can_be_donated() {
+        let mut context = get_context(accounts(2));
+        let mut contract = setup(&mut context);
+        transfer(&mut context, &mut contract, "dai.near".parse().unwrap(), accounts(5), 1000);
+    }
+
+    #[test]
+    fn test_failed_claim_is_claimable_again() {
+        let mut context = get_context(accounts(2));
+        let mut contract = setup(&mut context);
+        transfer(&mut context, &mut contract, usdc(), accounts(5), 1000);
+
+        testing_env!(context.predecessor_account_id(accounts(1)).attached_deposit(1).build());
+        contract.claim_token_donations(usdc());
+        assert_eq!(contract.claimable_token_donations(usdc(), accounts(1)).0, 0);
+
+        testing_env!(
+            context.predecessor_account_id(accounts(0)).attached_deposit(0).build(),
+            VMConfig::test(),
+            RuntimeFeesConfig::test(),
+            Default::default(),
+            vec![PromiseResult::Failed],
+        );
+        assert_eq!(contract.on_token_donation_claimed(usdc(), accounts(1), 666.into()).0, 0);
+        assert_eq!(contract.claimable_token_donations(usdc(), accounts(1)).0, 666);
+    }
+
+    /// Accepts and donates `MAX_DONATION_TOKENS` tokens, so every settlement touches all of them.
+    fn donate_all_tokens(context: &mut VMContextBuilder, contract: &mut Contract) -> Vec<AccountId> {
+        let token_ids: Vec<AccountId> = std::iter::once(usdc())
+            .chain((1..MAX_DONATION_TOKENS).map(|index| format!("token{}.near", index).parse().unwrap()))
+            .collect();
+        for token_id in token_ids.iter() {
+            call(context, accounts(2), SAFE_STORAGE_COST);
+            contract.add_donation_token(token_id.clone());
+            transfer(context, contract, token_id.clone(), accounts(5), 1000);
+        }
+        token_ids
+    }
+
+    /// Unit tests only count the gas of host functions, mostly storage access, so the bound leaves
+    /// plenty of room for the execution of the code itself.
+    #[test]
+    fn test_credit_settles_all_tokens_within_gas() {
+        let mut context = get_context(accounts(2));
+        let mut contract = setup(&mut context);
+        donate_all_tokens(&mut context, &mut contract);
+
+        call(&mut context, accounts(1), SAFE_STORAGE_COST);
+        contract.credit(1);
+        assert!(env::used_gas() < Gas(10_000_000_000_000), "A credit used {:?}.", env::used_gas());
+    }
+
+    #[test]
+    fn test_registration_pays_donation_storage() {
+        let mut context = get_context(accounts(2));
+        let mut contract = setup(&mut context);
+        let token_ids = donate_all_tokens(&mut context, &mut contract);
+
+        let min_balance = contract.storage_balance_bounds().min.0;
+        call(&mut context, accounts(0), min_balance);
+        let initial_storage_usage = env::storage_usage();
+        contract.storage_deposit(None, None);
+        contract.internal_mint_reputation(&accounts(0), 1, "Reputation for the test");
+        for token_id in token_ids {
+            assert!(contract.token_donation_accounts.contains_key(&(token_id, accounts(0))));
+        }
+        let used = Balance::from(env::storage_usage() - initial_storage_usage) * env::storage_byte_cost();
+        assert!(used <= min_balance, "The registration paid {} for {} of storage.", min_balance, used);
+    }
+}
@@ -0,0 +1,138 @@
+//! Verification of deeds by a quorum of creditors.
+//!
+//! Admins can set a threshold of distinct creditors or of DEED earned by a deed. An active deed
+//! that reaches the threshold becomes verified. A verified deed can be credited like an active
+//! one, but it falls back to active when withdrawn credits bring it below the threshold again.
+//! Changing the threshold does not affect existing deeds until they are credited or uncredited.
+
+use crate::*;
+use crate::access_control::Role;
+
+#[derive(BorshDeserialize, BorshSerialize, Deserialize, Serialize, Clone, PartialEq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum VerificationThreshold {
+    /// Number of distinct creditors.
+    Creditors { count: u64 },
+    /// DEED the deed earned with its credits.
+    Weight { amount: U128 },
+}
+
+impl VerificationThreshold {
+    fn is_reached_by(&self, deed: &SocialDeed) -> bool {
+        match self {
+            VerificationThreshold::Creditors { count } => deed.creditors.len() >= *count,
+            VerificationThreshold::Weight { amount } => deed.earned_reputation() >= amount.0,
+        }
+    }
+}
+
+impl Contract {
+    /// Verifies `deed` or withdraws its verification depending on the current threshold.
+    pub(crate) fn internal_update_verification(&self, deed: &mut SocialDeed) {
+        let reached = self.verification_threshold.as_ref().is_some_and(|threshold| threshold.is_reached_by(deed));
+        match deed.status {
+            DeedStatus::Active if reached => {
+                deed.status = DeedStatus::Verified;
+                deed.updated_at = env::block_timestamp();
+                deed.verified_at = Some(env::block_timestamp());
+                events::DeedVerified { deed_id: deed.id, author: &deed.author }.emit();
+            }
+            DeedStatus::Verified if !reached => {
+                deed.status = DeedStatus::Active;
+                deed.updated_at = env::block_timestamp();
+                deed.verified_at = None;
+                events::DeedUnverified { deed_id: deed.id, author: &deed.author }.emit();
+            }
+            _ => {}
+        }
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Sets the threshold after which deeds are verified. `None` disables the verification of new
+    /// deeds. Can only be called by admins.
+    pub fn set_verification_threshold(&mut self, threshold: Option<VerificationThreshold>) {
+        self.assert_role(Role::Admin);
+        self.verification_threshold = threshold;
+    }
+
+    pub fn get_verification_threshold(&self) -> Option<VerificationThreshold> {
+        self.verification_threshold.clone()
+    }
+
+    /// Returns the block timestamp at which the deed `id` was verified.
+    pub fn deed_verified_at(&self, id: u64) -> Option<U64> {
+        self.internal_get_deed(id).verified_at.map(U64::from)
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::testing_env;
+
+    use crate::tests::{call, get_context, SAFE_STORAGE_COST, TOTAL_SUPPLY};
+    use super::*;
+
+    /// Sets up a contract verifying deeds with two creditors and two deeds of accounts(1).
+    fn setup(context: &mut VMContextBuilder) -> Contract {
+        testing_env!(context.block_timestamp(0).build());
+        let mut contract = Contract::new_default_meta(accounts(2), TOTAL_SUPPLY.into());
+        contract.set_verification_threshold(Some(VerificationThreshold::Creditors { count: 2 }));
+        call(context, accounts(1), SAFE_STORAGE_COST);
+        contract.storage_deposit(None, None);
+        contract.add_deed(accounts(1), "title".into(), "description".into(), "proof".into(), None, None);
+        contract.add_deed(accounts(1), "title".into(), "description".into(), "proof".into(), None, None);
+        contract
+    }
+
+    #[test]
+    fn test_deed_is_verified_at_threshold() {
+        let mut context = get_context(accounts(2));
+        let mut contract = setup(&mut context);
+        call(&mut context, accounts(3), SAFE_STORAGE_COST);
+        contract.credit(0);
+        assert_eq!(contract.internal_get_deed(0).status, DeedStatus::Active);
+        call(context.block_timestamp(42), accounts(4), SAFE_STORAGE_COST);
+        contract.credit(0);
+
+        assert_eq!(contract.internal_get_deed(0).status, DeedStatus::Verified);
+        assert_eq!(contract.deed_verified_at(0), Some(42.into()));
+        let deeds = contract.social_deeds(accounts(3), None, None, None, Some(true), None).deeds;
+        assert_eq!(deeds.len(), 1);
+        assert_eq!(deeds[0].id, 0);
+        assert_eq!(contract.social_deeds(accounts(3), None, None, None, None, None).deeds.len(), 2);
+
+        call(&mut context, accounts(5), SAFE_STORAGE_COST);
+        contract.credit(0);
+        assert_eq!(contract.internal_get_deed(0).status, DeedStatus::Verified);
+    }
+
+    #[test]
+    fn test_uncredit_below_threshold_withdraws_verification() {
+        let mut context = get_context(accounts(2));
+        let mut contract = setup(&mut context);
+        call(&mut context, accounts(3), SAFE_STORAGE_COST);
+        contract.credit(0);
+        call(&mut context, accounts(4), SAFE_STORAGE_COST);
+        contract.credit(0);
+
+        call(&mut context, accounts(4), 0);
+        contract.uncredit(0);
+        assert_eq!(contract.internal_get_deed(0).status, DeedStatus::Active);
+        assert_eq!(contract.deed_verified_at(0), None);
+    }
+
+    #[test]
+    fn test_weight_threshold() {
+        let mut context = get_context(accounts(2));
+        let mut contract = setup(&mut context);
+        call(&mut context, accounts(2), 0);
+        contract.set_verification_threshold(Some(VerificationThreshold::Weight { amount: 1.into() }));
+        call(&mut context, accounts(3), SAFE_STORAGE_COST);
+        contract.credit(1);
+        assert_eq!(contract.internal_get_deed(1).status, DeedStatus::Verified);
+    }
+}
@@ -88,6 +88,15 @@ const App = ({ contract, currentUser, nearConfig, wallet, provider, lastTransact
       console.log("Successfully donated.");
     })
   };
+
+  const onClaim = async (_) => {
+    contract.claim_donations(
+      { },
+      BOATLOAD_OF_GAS
+    ).then((_) => {
+      console.log("Successfully claimed.");
+    })
+  };
   
   useEffect(() => {      
     const fetchRegistered = async () => {
@@ -111,7 +120,7 @@ const App = ({ contract, currentUser, nearConfig, wallet, provider, lastTransact
   
   const signIn = () => {
     wallet.requestSignIn(
-      {contractId: nearConfig.contractName, methodNames: [contract.add_deed.name, contract.credit.name, contract.donate.name]}, //contract requesting access
+      {contractId: nearConfig.contractName, methodNames: [contract.add_deed.name, contract.credit.name, contract.donate.name, contract.claim_donations.name]}, //contract requesting access
       'NEAR Challenge #5 - Social Bounty Hunt', //optional name
       null, //optional URL to redirect to if the sign in was successful
       null //optional URL to redirect to if the sign in was NOT successful
@@ -154,7 +163,7 @@ const App = ({ contract, currentUser, nearConfig, wallet, provider, lastTransact
         <Route path="donate" element={
           currentUser
             ? registered
-                ? <Donate onDonate={onDonate}/>
+                ? <Donate currentUser={currentUser} contract={contract} onDonate={onDonate} onClaim={onClaim}/>
                 : <Register onRegister={onRegister} />
             : <SignIn signIn={signIn}/>
         }/>
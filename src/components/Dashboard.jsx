@@ -20,7 +20,7 @@ const Dashboard = ({version, currentUser}) => {
                       DEED is a non-transferable and therefore non-tradable fungible token. Is represents the users social reputation
                       for his good deeds. But there is a second utility to the token. Users can also donate NEAR to the app. When they
                       do that, the donated NEAR is distributed to all DEED holders proprtional to they DEED amount. The donation itself
-                      will automatically create a new deed for the donator.
+                      is recorded in the donation history and the biggest donors are listed on a leaderboard.
                   </p>
                   <p>
                       They idea behind that was, that there are two kinds of users. Those who do good social deeds. Those who want to
@@ -33,7 +33,7 @@ const Dashboard = ({version, currentUser}) => {
                   </p>
                  <h5>Head over <Link className="menu-item" to="/publish">here</Link> to publish your first social 
                      deed. Or look at social deeds others have published so far <Link className="menu-item" to="/overview">here</Link>.
-                     Lastly you can <Link className="menu-item" to="/donate">donate</Link> NEAR to all DEED holders and automatically create a deed with that.
+                     Lastly you can <Link className="menu-item" to="/donate">donate</Link> NEAR to all DEED holders.
                  </h5>
              </>
 }
@@ -1,7 +1,19 @@
-import React from 'react';
+import React, { useState, useEffect } from 'react';
+import Big from 'big.js';
 import { Button, TextInput } from 'react-materialize';
 
-export default function Donate({onDonate}) {
+export default function Donate({currentUser, contract, onDonate, onClaim}) {
+  const [claimable, setClaimable] = useState('0');
+
+  useEffect(() => {
+      async function fetchClaimable() {
+          const result = await contract.claimable_donations({account_id: currentUser.accountId});
+          setClaimable(result);
+      }
+
+      fetchClaimable();
+  }, [contract, currentUser]);
+
   return (
     <>
       <header>
@@ -12,8 +24,8 @@ export default function Donate({onDonate}) {
             <p>Here you can donate NEAR.</p>
             <p>
                 When you hit Donate the chosen amount of NEAR will be distributed to all DEED holders.
-                This excludes your own account as you are doing the donation. Secondly a new deed is 
-                automatically created which shows that you donated the chosen amount.
+                This excludes your own account as you are doing the donation. Your donation is recorded in the
+                donation history and counts towards your place among the top donors.
             </p>
             <p className="highlight">
             <TextInput
@@ -35,6 +47,19 @@ export default function Donate({onDonate}) {
             </Button>
         </fieldset>
       </form>
+      <fieldset>
+          <p>
+              As a DEED holder you receive your share of every donation. Your share is kept by the
+              contract until you claim it.
+          </p>
+          <p className="highlight">
+              You can claim {Big(claimable).div(10 ** 24).toFixed()} Ⓝ.
+          </p>
+          <Button small disabled={claimable === '0'} onClick={onClaim}
+                  tooltip="Transfers the donations you received to your account.">
+            Claim
+          </Button>
+      </fieldset>
     </>
   );
 }
\ No newline at end of file
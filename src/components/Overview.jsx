@@ -2,18 +2,25 @@ import React, { useState, useEffect } from 'react';
 import PropTypes from 'prop-types';
 import { Button } from 'react-materialize';
 
+const PAGE_SIZE = 50;
+
 const Overview = ({currentUser, contract, onCredit}) => {
     const [deeds, setDeeds] = useState([]);
   
   useEffect(() => {
       async function fetchData() {
-          const count = await contract.get_deeds_count();
-          const result = await contract.social_deeds(
-          {
-              creditor_id: currentUser.accountId,
-              from_index: "0",
-              limit: parseInt(count)
-          });
+          const result = [];
+          let cursor = null;
+          do {
+              const page = await contract.social_deeds(
+              {
+                  creditor_id: currentUser.accountId,
+                  from_index: cursor,
+                  limit: PAGE_SIZE
+              });
+              result.push(...page.deeds);
+              cursor = page.next_cursor;
+          } while (cursor !== null);
           console.log(result);
           setDeeds(splitArrayIntoChunksOfLen(result, 2));
       }
@@ -82,9 +89,8 @@ Overview.propTypes = {
     balance: PropTypes.string.isRequired
   }),
   contract: PropTypes.shape({
-    get_deeds_count: PropTypes.func.isRequired,
     social_deeds: PropTypes.func.isRequired
   }).isRequired,
 };
 
-export default Overview;
\ No newline at end of file
+export default Overview;
\ No newline at end of file
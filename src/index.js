@@ -43,9 +43,9 @@ async function initContract() {
     nearConfig.contractName,
     {
       // View methods are read-only – they don't modify the state, but usually return some value
-      viewMethods: ['is_registered', 'storage_balance_bounds', 'get_deeds_count', 'social_deeds'],
+      viewMethods: ['is_registered', 'storage_balance_bounds', 'get_deeds_count', 'social_deeds', 'claimable_donations'],
       // Change methods can modify the state, but you don't receive the returned value when called
-      changeMethods: ['storage_deposit', 'add_deed', 'credit', 'donate'],
+      changeMethods: ['storage_deposit', 'add_deed', 'credit', 'donate', 'claim_donations'],
       // Sender is the account ID to initialize transactions.
       // getAccountId() will return empty string if user is still unauthorized
       sender: walletConnection.getAccountId(),